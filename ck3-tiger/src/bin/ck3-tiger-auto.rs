@@ -6,8 +6,8 @@ use anyhow::{bail, Result};
 use console::Term;
 
 use tiger_lib::{
-    emit_reports, find_game_directory_steam, find_paradox_directory, set_output_file, Everything,
-    Game, ModFile,
+    emit_reports_html, find_game_directory_steam, find_paradox_directory, set_output_file,
+    Everything, Game, ModFile,
 };
 
 /// Steam's code for Crusader Kings 3
@@ -128,26 +128,22 @@ fn validate_mod(ck3: &Path, modpath: &Path, logdir: &Path) -> Result<()> {
     eprintln!("Using mod directory: {}", modpath.display());
 
     let output_filename =
-        format!("ck3-tiger-{}.log", modpath.file_name().unwrap().to_string_lossy());
+        format!("ck3-tiger-{}.html", modpath.file_name().unwrap().to_string_lossy());
     let output_file = &logdir.join(output_filename);
     set_output_file(output_file)?;
     eprintln!("Writing error reports to {} ...", output_file.display());
-    eprintln!("This will take a few seconds.");
+    eprintln!("This will take a few seconds. Open the file in a web browser to read it.");
 
     let mut everything = Everything::new(Some(ck3), &modpath, modfile.replace_paths())?;
 
-    // Unfortunately have to disable the colors by default because
-    // on Windows there's no easy way to view a file that contains those escape sequences.
-    // There are workarounds but those defeat the purpose of -auto.
-    // The colors can be enabled again in the ck3-tiger.conf file.
-    everything.load_output_settings(false);
+    // The reports are written as an HTML page, because on Windows there's no easy way to view
+    // a text file that contains color escape sequences.
     everything.load_config_filtering_rules();
-    emit_reports(false);
 
     everything.load_all();
     everything.validate_all();
     everything.check_rivers();
-    emit_reports(false);
+    emit_reports_html();
 
     // Properly dropping `everything` takes a noticeable amount of time, and we're exiting anyway.
     forget(everything);
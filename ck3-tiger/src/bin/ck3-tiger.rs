@@ -5,8 +5,9 @@ use anyhow::{bail, Result};
 use clap::Parser;
 
 use tiger_lib::{
-    disable_ansi_colors, emit_reports, find_game_directory_steam, set_show_loaded_mods,
-    set_show_vanilla, Everything, Game, ModFile,
+    collect_abbreviated_reports, disable_ansi_colors, emit_reports, emit_reports_html,
+    find_game_directory_steam, open_mod_archive, path_exists, set_show_loaded_mods,
+    set_show_vanilla, Everything, Game, ModFile, Summary,
 };
 
 /// Steam's code for Crusader Kings 3
@@ -21,6 +22,7 @@ const CK3_SIGNATURE_FILE: &str = "game/events/witch_events.txt";
 #[derive(Parser)]
 struct Cli {
     /// Path to .mod file of mod to check.
+    /// Can also be the mod's directory, or a .zip archive of the mod.
     modpath: PathBuf,
     /// Path to CK3 main directory.
     #[clap(long)]
@@ -34,9 +36,31 @@ struct Cli {
     /// Output the reports in JSON format
     #[clap(long)]
     json: bool,
+    /// Output the reports as an HTML page that can group and filter them
+    #[clap(long, conflicts_with = "json")]
+    html: bool,
+    /// Print summary statistics of the run, such as report counts and timings
+    #[clap(long)]
+    summary: bool,
+    /// Write the summary statistics to this file as JSON
+    #[clap(long)]
+    summary_json: Option<PathBuf>,
+    /// Print how the summary statistics changed since a previous run that wrote this file
+    #[clap(long)]
+    previous_summary: Option<PathBuf>,
     /// Warn about items that are defined but unused
     #[clap(long)]
     unused: bool,
+    /// Print how many of the mod's localization keys are translated to each language
+    #[clap(long)]
+    loca_coverage: bool,
+    /// Write stub localization files for untranslated keys into this directory
+    #[clap(long)]
+    loca_stubs: Option<PathBuf>,
+    /// Print the fully resolved widget tree of this gui window, widget or type,
+    /// showing which file and line each part came from
+    #[clap(long)]
+    gui_tree: Option<String>,
     /// Do checks specific to the Princes of Darkness mod
     #[clap(long)]
     pod: bool,
@@ -94,14 +118,17 @@ fn main() -> Result<()> {
     }
 
     if args.unused {
-        eprintln!("Showing warnings for unused localization. There will be many false positives.");
+        eprintln!("Showing warnings for unused localization, scripted items, gui items and assets. There will be many false positives.");
     }
 
     if args.pod {
         eprintln!("Doing special checks for the Princes of Darkness mod.");
     }
 
-    if args.modpath.is_dir() {
+    if args.modpath.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("zip")) {
+        eprintln!("Reading mod from archive: {}", args.modpath.display());
+        args.modpath = open_mod_archive(&args.modpath)?.join("descriptor.mod");
+    } else if args.modpath.is_dir() {
         args.modpath.push("descriptor.mod");
     }
     if args.no_color {
@@ -110,7 +137,7 @@ fn main() -> Result<()> {
     }
     let modfile = ModFile::read(&args.modpath)?;
     let modpath = modfile.modpath();
-    if !modpath.exists() {
+    if !path_exists(&modpath) {
         eprintln!("Looking for mod in {}", modpath.display());
         bail!("Cannot find mod directory. Please make sure the .mod file is correct.");
     }
@@ -123,7 +150,7 @@ fn main() -> Result<()> {
 
     everything.load_output_settings(true);
     everything.load_config_filtering_rules();
-    if !args.json {
+    if !args.json && !args.html {
         emit_reports(false);
     }
 
@@ -145,9 +172,45 @@ fn main() -> Result<()> {
     if args.pod {
         everything.check_pod();
     }
-    emit_reports(args.json);
-    if args.unused {
-        everything.check_unused();
+    if args.html {
+        if args.unused {
+            // The unused items are listed at the end of the page
+            collect_abbreviated_reports();
+            everything.check_unused();
+        }
+        emit_reports_html();
+    } else {
+        emit_reports(args.json);
+        if args.unused {
+            everything.check_unused();
+        }
+    }
+    if args.summary || args.summary_json.is_some() || args.previous_summary.is_some() {
+        let summary = everything.summary();
+        if args.summary {
+            eprint!("{summary}");
+        }
+        if let Some(path) = &args.previous_summary {
+            eprint!("{}", summary.deltas(&Summary::read(path)?));
+        }
+        if let Some(path) = &args.summary_json {
+            summary.write(path)?;
+        }
+    }
+    if args.loca_coverage {
+        print!("{}", everything.loca_coverage_report());
+    }
+    if let Some(dir) = &args.loca_stubs {
+        for path in everything.write_loca_stubs(dir)? {
+            eprintln!("Wrote localization stub {}", path.display());
+        }
+    }
+    if let Some(name) = &args.gui_tree {
+        if let Some(tree) = everything.gui_tree(name) {
+            print!("{tree}");
+        } else {
+            eprintln!("No gui window, widget or type named {name}");
+        }
     }
 
     // Properly dropping `everything` takes a noticeable amount of time, and we're exiting anyway.
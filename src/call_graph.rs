@@ -0,0 +1,367 @@
+//! A graph of the calls between scripted effects, scripted triggers, script values, events, and
+//! `on_actions`, used to find cycles in it.
+//!
+//! Scripted effects, scripted triggers, and script values are expanded where they are used, so
+//! any recursion among them is fatal regardless of conditions. Events and `on_actions` that fire
+//! each other without a delay are only a problem if nothing stops the loop, so they get a warning.
+
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+
+use fnv::FnvHashMap;
+
+use crate::block::{Block, BlockItem, Field, BV};
+use crate::everything::Everything;
+use crate::report::{err, warn, ErrorKey};
+use crate::token::Token;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Kind {
+    Effect,
+    Trigger,
+    ScriptValue,
+    Event,
+    OnAction,
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            Kind::Effect => write!(f, "scripted effect"),
+            Kind::Trigger => write!(f, "scripted trigger"),
+            Kind::ScriptValue => write!(f, "script value"),
+            Kind::Event => write!(f, "event"),
+            Kind::OnAction => write!(f, "on_action"),
+        }
+    }
+}
+
+/// The `on_action` fields that list events to fire.
+const ON_ACTION_EVENTS: &[&str] = &["events", "random_events", "first_valid"];
+/// The `on_action` fields that list other `on_actions` to fire.
+const ON_ACTION_CHAINS: &[&str] = &["on_actions", "random_on_action", "first_valid_on_action"];
+/// The event fields whose effects run as soon as the event fires.
+const EVENT_IMMEDIATE: &[&str] = &["immediate", "on_trigger_fail"];
+/// The event fields whose effects only run right away if the event is hidden.
+/// Otherwise they wait for the player to pick an option.
+const EVENT_OPTIONS: &[&str] = &["option", "after"];
+/// The `trigger_event` fields that postpone the event.
+const TRIGGER_EVENT_DELAYS: &[&str] = &["days", "weeks", "months", "years", "trigger_on_next_date"];
+
+#[derive(Debug, Default)]
+struct CallGraph<'a> {
+    nodes: Vec<(Kind, &'a Token)>,
+    index: FnvHashMap<(Kind, &'a str), usize>,
+    /// The calls made by each node, as the callee's kind and name and the token that calls it.
+    calls: Vec<Vec<(Kind, &'a str, &'a Token)>>,
+    /// The calls resolved to node indexes.
+    edges: Vec<Vec<(usize, &'a Token)>>,
+}
+
+impl<'a> CallGraph<'a> {
+    fn add_node(&mut self, kind: Kind, key: &'a Token) -> usize {
+        let idx = self.nodes.len();
+        self.nodes.push((kind, key));
+        self.index.insert((kind, key.as_str()), idx);
+        self.calls.push(Vec::new());
+        idx
+    }
+
+    fn add_call(&mut self, from: usize, kind: Kind, token: &'a Token) {
+        self.calls[from].push((kind, token.as_str(), token));
+    }
+
+    /// Record the calls made by the effects in `block`.
+    fn scan_effect(&mut self, from: usize, block: &'a Block, data: &Everything) {
+        for item in block.iter_items() {
+            let BlockItem::Field(Field(key, _, bv)) = item else {
+                if let BlockItem::Block(block) = item {
+                    self.scan_effect(from, block, data);
+                }
+                continue;
+            };
+            if data.effects.exists(key.as_str()) {
+                self.add_call(from, Kind::Effect, key);
+            } else if key.lowercase_is("trigger_event") {
+                match bv {
+                    BV::Value(token) => self.add_call(from, Kind::Event, token),
+                    BV::Block(block) => {
+                        let delayed = TRIGGER_EVENT_DELAYS.iter().any(|f| block.has_key(f))
+                            || block.get_field_bool("delayed").unwrap_or(false);
+                        if !delayed {
+                            if let Some(token) = block.get_field_value("id") {
+                                self.add_call(from, Kind::Event, token);
+                            }
+                            if let Some(token) = block.get_field_value("on_action") {
+                                self.add_call(from, Kind::OnAction, token);
+                            }
+                        }
+                    }
+                }
+                continue;
+            }
+            if let BV::Block(block) = bv {
+                self.scan_effect(from, block, data);
+            }
+        }
+    }
+
+    /// Record the calls made by the triggers or script value in `bv`.
+    fn scan_trigger(&mut self, from: usize, bv: &'a BV, data: &Everything) {
+        match bv {
+            BV::Value(token) => self.scan_script_value_token(from, token, data),
+            BV::Block(block) => self.scan_trigger_block(from, block, data),
+        }
+    }
+
+    fn scan_trigger_block(&mut self, from: usize, block: &'a Block, data: &Everything) {
+        for item in block.iter_items() {
+            match item {
+                BlockItem::Value(token) => self.scan_script_value_token(from, token, data),
+                BlockItem::Block(block) => self.scan_trigger_block(from, block, data),
+                BlockItem::Field(Field(key, _, bv)) => {
+                    if data.triggers.exists(key.as_str()) {
+                        self.add_call(from, Kind::Trigger, key);
+                    }
+                    self.scan_script_value_token(from, key, data);
+                    self.scan_trigger(from, bv, data);
+                }
+            }
+        }
+    }
+
+    fn scan_script_value_token(&mut self, from: usize, token: &'a Token, data: &Everything) {
+        // Script values with arguments look like `value:name|ARG|val|`
+        let name = token.as_str().strip_prefix("value:").unwrap_or(token.as_str());
+        let name = name.split('|').next().unwrap_or(name);
+        if data.script_values.exists(name) {
+            self.calls[from].push((Kind::ScriptValue, name, token));
+        }
+    }
+
+    fn resolve(&mut self) {
+        self.edges = self
+            .calls
+            .iter()
+            .map(|calls| {
+                calls
+                    .iter()
+                    .filter_map(|&(kind, name, token)| {
+                        self.index.get(&(kind, name)).map(|&idx| (idx, token))
+                    })
+                    .collect()
+            })
+            .collect();
+    }
+
+    /// Find the strongly connected components of the graph with Tarjan's algorithm.
+    /// It is done iteratively because call chains can be too deep for the stack.
+    fn components(&self) -> Vec<Vec<usize>> {
+        let n = self.nodes.len();
+        let mut index = vec![usize::MAX; n];
+        let mut low = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut stack = Vec::new();
+        let mut next = 0;
+        let mut components = Vec::new();
+
+        for root in 0..n {
+            if index[root] != usize::MAX {
+                continue;
+            }
+            index[root] = next;
+            low[root] = next;
+            next += 1;
+            stack.push(root);
+            on_stack[root] = true;
+            let mut work = vec![(root, 0)];
+            while let Some((v, i)) = work.pop() {
+                if let Some(&(w, _)) = self.edges[v].get(i) {
+                    work.push((v, i + 1));
+                    if index[w] == usize::MAX {
+                        index[w] = next;
+                        low[w] = next;
+                        next += 1;
+                        stack.push(w);
+                        on_stack[w] = true;
+                        work.push((w, 0));
+                    } else if on_stack[w] {
+                        low[v] = low[v].min(index[w]);
+                    }
+                    continue;
+                }
+                if let Some(&(parent, _)) = work.last() {
+                    low[parent] = low[parent].min(low[v]);
+                }
+                if low[v] == index[v] {
+                    let mut component = Vec::new();
+                    while let Some(w) = stack.pop() {
+                        on_stack[w] = false;
+                        component.push(w);
+                        if w == v {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+            }
+        }
+        components
+    }
+
+    /// Find a shortest cycle from `start` back to itself, staying within `component`.
+    /// Return the calls that make up the cycle.
+    fn cycle(&self, start: usize, component: &[usize]) -> Vec<(usize, usize, &'a Token)> {
+        let mut came_from: FnvHashMap<usize, (usize, &Token)> = FnvHashMap::default();
+        let mut queue = VecDeque::from([start]);
+        while let Some(v) = queue.pop_front() {
+            for &(w, token) in &self.edges[v] {
+                if !component.contains(&w) || came_from.contains_key(&w) {
+                    continue;
+                }
+                came_from.insert(w, (v, token));
+                if w == start {
+                    let mut cycle = Vec::new();
+                    let mut to = start;
+                    while let Some(&(from, token)) = came_from.get(&to) {
+                        cycle.push((from, to, token));
+                        to = from;
+                        if to == start {
+                            break;
+                        }
+                    }
+                    cycle.reverse();
+                    return cycle;
+                }
+                queue.push_back(w);
+            }
+        }
+        Vec::new()
+    }
+
+    fn report_cycles(&self) {
+        for mut component in self.components() {
+            if component.len() == 1
+                && !self.edges[component[0]].iter().any(|&(w, _)| w == component[0])
+            {
+                continue;
+            }
+            // Start from the same node every run, so that the report is stable
+            component.sort_by_key(|&idx| (self.nodes[idx].0, self.nodes[idx].1.as_str()));
+            let start = component[0];
+            let cycle = self.cycle(start, &component);
+            let Some(&(_, _, first)) = cycle.first() else {
+                continue;
+            };
+
+            let (kind, key) = self.nodes[start];
+            let delayable = cycle
+                .iter()
+                .any(|&(from, _, _)| matches!(self.nodes[from].0, Kind::Event | Kind::OnAction));
+            let builder = if delayable { warn(ErrorKey::Loop) } else { err(ErrorKey::Loop) };
+            let mut info = if delayable {
+                "this fires forever unless a trigger breaks the loop; consider adding a delay"
+            } else {
+                "these are expanded where they are used, so recursion will crash the game"
+            }
+            .to_string();
+            let msg = if cycle.len() == 1 {
+                format!("{kind} `{key}` calls itself")
+            } else {
+                let via: Vec<String> = cycle[1..]
+                    .iter()
+                    .map(|&(from, _, _)| format!("{} `{}`", self.nodes[from].0, self.nodes[from].1))
+                    .collect();
+                info = format!("via {}; {info}", via.join(", "));
+                format!("{kind} `{key}` calls itself through a loop")
+            };
+            let mut report = builder.msg(msg).info(info).loc(first);
+            for &(from, to, token) in &cycle[1..] {
+                let (from_kind, from_key) = self.nodes[from];
+                let (to_kind, to_key) = self.nodes[to];
+                let msg = format!("{from_kind} `{from_key}` calls {to_kind} `{to_key}`");
+                report = report.loc(token, msg);
+            }
+            report.push();
+        }
+    }
+}
+
+/// Build the call graph of the loaded scripted items, events, and `on_actions`, and report the
+/// loops in it.
+pub fn check_call_graph(data: &Everything) {
+    let mut graph = CallGraph::default();
+    let mut effects = Vec::new();
+    let mut triggers = Vec::new();
+    let mut values = Vec::new();
+    let mut events = Vec::new();
+    let mut on_actions = Vec::new();
+
+    for (key, block) in data.effects.iter_definitions() {
+        effects.push((graph.add_node(Kind::Effect, key), block));
+    }
+    for (key, block) in data.triggers.iter_definitions() {
+        triggers.push((graph.add_node(Kind::Trigger, key), block));
+    }
+    for (key, bv) in data.script_values.iter_definitions() {
+        values.push((graph.add_node(Kind::ScriptValue, key), bv));
+    }
+    for (key, block) in data.iter_event_definitions() {
+        events.push((graph.add_node(Kind::Event, key), block));
+    }
+    for (key, block) in data.on_actions.iter_definitions() {
+        on_actions.push((graph.add_node(Kind::OnAction, key), block));
+    }
+
+    for (idx, block) in effects {
+        graph.scan_effect(idx, block, data);
+    }
+    for (idx, block) in triggers {
+        graph.scan_trigger_block(idx, block, data);
+    }
+    for (idx, bv) in values {
+        graph.scan_trigger(idx, bv, data);
+    }
+    for (idx, block) in events {
+        let hidden = block.get_field_bool("hidden").unwrap_or(false);
+        for (key, block) in block.iter_definitions() {
+            if EVENT_IMMEDIATE.contains(&key.as_str())
+                || (hidden && EVENT_OPTIONS.contains(&key.as_str()))
+            {
+                graph.scan_effect(idx, block, data);
+            }
+        }
+    }
+    for (idx, block) in on_actions {
+        for (key, block) in block.iter_definitions() {
+            let kind = if ON_ACTION_EVENTS.contains(&key.as_str()) {
+                Kind::Event
+            } else if ON_ACTION_CHAINS.contains(&key.as_str()) {
+                Kind::OnAction
+            } else {
+                if key.is("effect") {
+                    graph.scan_effect(idx, block, data);
+                }
+                continue;
+            };
+            if block.has_key("delay") {
+                continue;
+            }
+            for token in block.iter_values() {
+                graph.add_call(idx, kind, token);
+            }
+            for (key, token) in block.iter_assignments() {
+                if key.is_integer() {
+                    graph.add_call(idx, kind, token);
+                }
+            }
+        }
+        // The fallback only fires if nothing else did, but then it fires right away
+        if let Some(token) = block.get_field_value("fallback") {
+            graph.add_call(idx, Kind::OnAction, token);
+        }
+    }
+
+    graph.resolve();
+    graph.report_cycles();
+}
@@ -0,0 +1,210 @@
+//! [`CallSites`] is a helper for scripted effects and triggers. It records where they are called
+//! from and in what scope, so that after validation the call sites can be compared with each other.
+//!
+//! A scripted item whose call sites mostly agree on a scope type probably expects that scope
+//! type, even if its own body doesn't say so.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use fnv::{FnvHashMap, FnvHashSet};
+
+use crate::context::ScopeContext;
+use crate::report::{warn, ErrorKey};
+use crate::scopes::Scopes;
+use crate::token::{Loc, Token};
+
+/// Only compare call sites if there are at least this many with a known scope type.
+const MIN_CALL_SITES: usize = 3;
+
+#[derive(Clone, Debug)]
+struct CallSite {
+    key: Token,
+    /// The scope types of `this` at the call site.
+    scopes: Scopes,
+    /// The named scopes known at the call site, with their scope types.
+    names: FnvHashMap<String, Scopes>,
+    /// Whether the call site's named scopes are all known. If so, the scope context has already
+    /// reported any missing ones.
+    strict: bool,
+    /// The macro arguments given at the call site, if any.
+    args: Vec<(String, Token)>,
+}
+
+#[derive(Debug, Default)]
+pub struct CallSites {
+    /// The call sites, indexed by the loc of the calling key.
+    calls: Mutex<FnvHashMap<Loc, CallSite>>,
+    /// The scope types of `this` allowed by the scripted item's body, if it has been validated.
+    body_scopes: Mutex<Option<Scopes>>,
+    /// The named scopes that the scripted item's body expects to be set.
+    inputs: Mutex<FnvHashSet<String>>,
+    /// Whether the call sites are final. Calls made after that are not recorded.
+    frozen: AtomicBool,
+}
+
+impl CallSites {
+    /// Record a call of the scripted item `item`, from `key` with scope context `sc` and macro
+    /// arguments `args`. The scripted item validating itself does not count as a call, and neither
+    /// do calls made after [`CallSites::freeze`].
+    pub fn record(&self, item: &Token, key: &Token, args: &[(&str, Token)], sc: &ScopeContext) {
+        let mut loc = key.loc;
+        if loc == item.loc || self.frozen.load(Ordering::Relaxed) {
+            return;
+        }
+        // Calls from inside a macro expansion all count as the same call site
+        loc.link_idx = None;
+        let names: FnvHashMap<String, Scopes> = sc
+            .scope_names()
+            .filter_map(|name| sc.is_name_defined(name).map(|scopes| (name.to_string(), scopes)))
+            .collect();
+        let mut calls = self.calls.lock().unwrap();
+        // A call site in the body of another scripted item can be validated several times,
+        // so combine what is known about it.
+        if let Some(call) = calls.get_mut(&loc) {
+            call.scopes |= sc.scopes();
+            call.names
+                .retain(|name, scopes| names.get(name).map(|other| *scopes |= *other).is_some());
+            call.strict &= sc.is_strict();
+        } else {
+            let call = CallSite {
+                key: key.clone(),
+                scopes: sc.scopes(),
+                names,
+                strict: sc.is_strict(),
+                args: args.iter().map(|(parm, arg)| ((*parm).to_string(), arg.clone())).collect(),
+            };
+            calls.insert(loc, call);
+        }
+    }
+
+    /// Stop recording calls. This is done before the scripted items are validated on their own,
+    /// so that those validations all see the same call sites and don't add their own unrooted
+    /// calls to them.
+    pub fn freeze(&self) {
+        self.frozen.store(true, Ordering::Relaxed);
+    }
+
+    /// Record what the scripted item's body expects of its caller, after validating it with `sc`.
+    pub fn record_body(&self, sc: &ScopeContext) {
+        let mut body_scopes = self.body_scopes.lock().unwrap();
+        *body_scopes = Some(body_scopes.map_or(sc.scopes(), |scopes| scopes | sc.scopes()));
+        self.inputs.lock().unwrap().extend(sc.input_scope_names().map(str::to_string));
+    }
+
+    /// Return a scope context for validating the scripted item `item` on its own, based on what
+    /// its call sites provide. `this` can be any of the scope types it has at the call sites, and
+    /// the named scopes are the ones that are set at all of them.
+    /// Without call sites, nothing is known and `this` can be anything.
+    pub fn scope_context(&self, item: &Token) -> ScopeContext {
+        let calls = self.calls.lock().unwrap();
+        let mut sorted: Vec<_> = calls.values().collect();
+        sorted.sort_unstable_by_key(|call| call.key.loc);
+        let this = sorted.iter().fold(Scopes::empty(), |scopes, call| scopes | call.scopes);
+        let mut sc =
+            ScopeContext::new_unrooted(if this.is_empty() { Scopes::all() } else { this }, item);
+        sc.set_strict_scopes(false);
+        if let Some((first, rest)) = sorted.split_first() {
+            let mut names: Vec<_> = first.names.iter().collect();
+            names.sort_unstable_by_key(|(name, _)| *name);
+            'names: for (name, &scopes) in names {
+                let mut scopes = scopes;
+                for call in rest {
+                    let Some(&other) = call.names.get(name) else {
+                        continue 'names;
+                    };
+                    scopes |= other;
+                }
+                sc.define_name_token(name, scopes, &first.key);
+            }
+        }
+        sc
+    }
+
+    /// Return the distinct sets of macro arguments that the scripted item was called with,
+    /// each with the first call site that used them.
+    pub fn macro_calls(&self) -> Vec<(Token, Vec<(String, Token)>)> {
+        let calls = self.calls.lock().unwrap();
+        let mut sorted: Vec<_> = calls.values().filter(|call| !call.args.is_empty()).collect();
+        sorted.sort_unstable_by_key(|call| call.key.loc);
+        let mut result: Vec<(Token, Vec<(String, Token)>)> = Vec::new();
+        for call in sorted {
+            if !result.iter().any(|(_, args)| *args == call.args) {
+                result.push((call.key.clone(), call.args.clone()));
+            }
+        }
+        result
+    }
+
+    pub fn is_unused(&self) -> bool {
+        self.calls.lock().unwrap().is_empty()
+    }
+
+    /// Infer the scope type that the scripted item `item` expects from the majority of its call
+    /// sites, and report call sites that disagree with it. Also report call sites that lack a
+    /// named scope that the item uses and that most of the other call sites provide.
+    ///
+    /// `kind` is the kind of item, for use in messages.
+    pub fn check(&self, item: &Token, kind: &str) {
+        let calls = self.calls.lock().unwrap();
+        let body_scopes = self.body_scopes.lock().unwrap().unwrap_or(Scopes::all());
+
+        // Scopes is not hashable, and there are few distinct scope types among the call sites
+        let mut counts: Vec<(Scopes, usize)> = Vec::new();
+        for call in calls.values() {
+            if call.scopes.bits().is_power_of_two() {
+                if let Some((_, count)) = counts.iter_mut().find(|(s, _)| *s == call.scopes) {
+                    *count += 1;
+                } else {
+                    counts.push((call.scopes, 1));
+                }
+            }
+        }
+        let known: usize = counts.iter().map(|(_, count)| count).sum();
+        if known >= MIN_CALL_SITES {
+            if let Some(&(majority, count)) = counts.iter().max_by_key(|(_, count)| count) {
+                if count * 2 > known {
+                    for call in calls.values() {
+                        // If the body itself rules out the call's scope type, then the
+                        // scope context has already reported it.
+                        if !call.scopes.intersects(majority) && call.scopes.intersects(body_scopes)
+                        {
+                            let msg = format!(
+                                "{kind} `{item}` is called here in {} scope, but in {majority} scope at {count} of its {known} call sites",
+                                call.scopes
+                            );
+                            warn(ErrorKey::Scopes)
+                                .msg(msg)
+                                .loc(&call.key)
+                                .loc(item, "defined here")
+                                .push();
+                        }
+                    }
+                }
+            }
+        }
+
+        let total = calls.len();
+        if total >= MIN_CALL_SITES {
+            for name in &*self.inputs.lock().unwrap() {
+                let count = calls.values().filter(|call| call.names.contains_key(name)).count();
+                if count * 2 <= total {
+                    continue;
+                }
+                for call in calls.values() {
+                    if !call.strict && !call.names.contains_key(name) {
+                        let msg = format!(
+                            "{kind} `{item}` uses scope:{name}, which is set at {count} of its {total} call sites but not known to be set here"
+                        );
+                        warn(ErrorKey::StrictScopes)
+                            .weak()
+                            .msg(msg)
+                            .loc(&call.key)
+                            .loc(item, "defined here")
+                            .push();
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,4 +1,6 @@
-use crate::block::Block;
+use crate::block::{Block, BV};
+use crate::ck3::data::characters::Gender;
+use crate::ck3::data::titles::Tier;
 use crate::ck3::validate::validate_portrait_modifier_overrides;
 use crate::context::ScopeContext;
 use crate::data::dna::validate_genes;
@@ -6,6 +8,7 @@ use crate::date::Date;
 use crate::db::{Db, DbKind};
 use crate::everything::Everything;
 use crate::game::GameFlags;
+use crate::helpers::stringify_list;
 use crate::item::{Item, ItemLoader};
 use crate::pdxfile::PdxEncoding;
 use crate::report::{warn, warn_info, ErrorKey};
@@ -133,6 +136,7 @@ fn validate_bookmark_character(
         if let Some(id) = block.get_field_value("history_id") {
             let name = block.get_field_value("name");
             if data.item_exists(Item::Character, id.as_str()) {
+                validate_bookmark_state(block, id, start_date, data);
                 validate_bookmark_against_history(
                     block.get_field_value("dynasty"),
                     "dynasty",
@@ -164,11 +168,176 @@ fn validate_bookmark_character(
             }
         }
     }
+    if !block.field_value_is("display", "no") {
+        if let Some(name) = block.get_field_value("name") {
+            validate_bookmark_portrait(block, name, data);
+        }
+    }
+    validate_bookmark_animation(block, data);
     vd.multi_field_validated_block("character", |block, data| {
         validate_bookmark_character(block, data, false, start_date);
     });
 }
 
+/// Check that the portrait shown for a bookmark character exists and matches the character.
+fn validate_bookmark_portrait(block: &Block, name: &Token, data: &Everything) {
+    if let Some((_, portrait)) = data.database.get_key_block(Item::BookmarkPortrait, name.as_str())
+    {
+        if let (Some(bm_type), Some(portrait_type)) =
+            (block.get_field_value("type"), portrait.get_field_value("type"))
+        {
+            if bm_type != portrait_type {
+                let msg = format!("type is {bm_type} in bookmark but {portrait_type} in portrait");
+                warn(ErrorKey::Bookmarks)
+                    .msg(msg)
+                    .loc_msg(bm_type, "bookmark")
+                    .loc(portrait_type, "portrait")
+                    .push();
+            }
+        }
+        if let (Some(id), Some(genes)) =
+            (block.get_field_value("history_id"), portrait.get_field_block("genes"))
+        {
+            validate_portrait_genes(id, genes, data);
+        }
+    } else {
+        let msg = format!("no bookmark portrait for {name}");
+        let info = format!("expected `{name}` to be defined in {}", Item::BookmarkPortrait.path());
+        warn(ErrorKey::Bookmarks).weak().msg(msg).info(info).loc(name).push();
+    }
+}
+
+/// Check that the genes of a bookmark portrait are the ones the character will have in game.
+/// Only genes that are in both the portrait and the character's DNA are compared, and DNA given
+/// as a base64 string is skipped.
+fn validate_portrait_genes(id: &Token, genes: &Block, data: &Everything) {
+    if !data.item_exists(Item::Character, id.as_str()) {
+        return;
+    }
+    let Some(dna) = data.characters.get_dna(id) else {
+        let msg = format!("character {id} has no `dna` in history");
+        let info =
+            "their appearance will be generated in game and will not match the bookmark portrait";
+        warn(ErrorKey::Bookmarks).weak().msg(msg).info(info).loc(id).push();
+        return;
+    };
+    let Some(dna_genes) = data
+        .database
+        .get_key_block(Item::Dna, dna.as_str())
+        .and_then(|(_, block)| block.get_field_block("portrait_info"))
+        .and_then(|block| block.get_field_block("genes"))
+    else {
+        return;
+    };
+    let mut differ = Vec::new();
+    for (key, block) in genes.iter_definitions() {
+        if let Some(dna_block) = dna_genes.get_field_block(key.as_str()) {
+            if !block.iter_values().eq(dna_block.iter_values()) {
+                differ.push(key.as_str());
+            }
+        }
+    }
+    if !differ.is_empty() {
+        let msg = format!("bookmark portrait genes differ from dna {dna} of character {id}");
+        let info = format!("differing genes: {}", stringify_list(&differ));
+        warn(ErrorKey::Bookmarks).msg(msg).info(info).loc(genes).loc(dna, "dna").push();
+    }
+}
+
+/// Check that the portrait animation of a bookmark character has a version for its body type.
+/// Body types in a portrait animation may refer to another body type instead of having a block.
+fn validate_bookmark_animation(block: &Block, data: &Everything) {
+    let (Some(animation), Some(bm_type)) =
+        (block.get_field_value("animation"), block.get_field_value("type"))
+    else {
+        return;
+    };
+    let Some((_, anim_block)) =
+        data.database.get_key_block(Item::PortraitAnimation, animation.as_str())
+    else {
+        return;
+    };
+    let mut seen = vec![bm_type.as_str()];
+    loop {
+        let body_type = seen[seen.len() - 1];
+        match anim_block.get_field(body_type) {
+            Some(BV::Block(_)) => return,
+            Some(BV::Value(token)) if !seen.contains(&token.as_str()) => seen.push(token.as_str()),
+            _ => break,
+        }
+    }
+    let msg = format!("portrait animation {animation} has no animation for a {bm_type}");
+    let info = format!("followed body types {}", stringify_list(&seen));
+    warn(ErrorKey::Bookmarks).msg(msg).info(info).loc(animation).push();
+}
+
+/// Check a bookmark character against the state of the world on the bookmark's start date.
+fn validate_bookmark_state(block: &Block, id: &Token, date: Date, data: &Everything) {
+    if !data.characters.is_alive(id, date) {
+        let msg = format!("character {id} is not alive on the bookmark date {date}");
+        warn(ErrorKey::Bookmarks).strong().msg(msg).loc(id).push();
+        return;
+    }
+
+    if let Some(birth) = block.get_field_value("birth") {
+        if let (Ok(bm_birth), Some(history_birth)) =
+            (Date::try_from(birth), data.characters.get_birth_date(id))
+        {
+            if bm_birth != history_birth {
+                let msg = format!(
+                    "birth is {bm_birth} in bookmark but {history_birth} in character history"
+                );
+                warn(ErrorKey::Bookmarks).msg(msg).loc(birth).push();
+            }
+        }
+    }
+
+    if let Some(bm_type) = block.get_field_value("type") {
+        if let (Some(gender), Some(adult)) =
+            (data.characters.get_gender(id), data.characters.is_adult(id, date))
+        {
+            let expected = match (gender, adult) {
+                (Gender::Male, true) => "male",
+                (Gender::Female, true) => "female",
+                (Gender::Male, false) => "boy",
+                (Gender::Female, false) => "girl",
+            };
+            if !bm_type.is(expected) {
+                let msg = format!(
+                    "type is {bm_type} in bookmark but character is a {expected} at {date}"
+                );
+                warn(ErrorKey::Bookmarks).msg(msg).loc(bm_type).push();
+            }
+        }
+    }
+
+    if let Some(title) = block.get_field_value("title") {
+        let holder = data.title_history.holder_at(title.as_str(), date);
+        if holder != Some(id) {
+            let msg = format!("{id} does not hold {title} on {date}");
+            let info = match holder {
+                Some(holder) => format!("it is held by {holder}"),
+                None => "it has no holder".to_string(),
+            };
+            warn(ErrorKey::Bookmarks).strong().msg(msg).info(info).loc(title).push();
+        } else if let Ok(tier) = Tier::try_from(title) {
+            let highest = data
+                .title_history
+                .titles_held_at(id.as_str(), date)
+                .filter_map(|held| Tier::try_from(held).ok().map(|tier| (tier, held)))
+                .max_by_key(|(tier, _)| *tier);
+            if let Some((held_tier, held)) = highest {
+                if held_tier > tier {
+                    let msg =
+                        format!("bookmark shows {title}, but {id} also holds {held} on {date}");
+                    let info = format!("the {held_tier} is their primary title, not the {tier}");
+                    warn(ErrorKey::Bookmarks).msg(msg).info(info).loc(title).push();
+                }
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct BookmarkPortrait {}
 
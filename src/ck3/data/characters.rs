@@ -23,6 +23,13 @@ use crate::tooltipped::Tooltipped;
 use crate::validate::validate_color;
 use crate::validator::Validator;
 
+/// Parents younger than this at the birth of their child are implausible.
+const MIN_PARENT_AGE: i16 = 12;
+/// Mothers older than this at the birth of their child are implausible.
+const MAX_MOTHER_AGE: i16 = 60;
+/// The age at which characters become adults and can marry.
+const ADULT_AGE: i16 = 16;
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Gender {
     Male,
@@ -137,6 +144,23 @@ impl Characters {
         })
     }
 
+    pub fn get_gender(&self, id: &Token) -> Option<Gender> {
+        self.characters.get(id.as_str()).map(Character::gender)
+    }
+
+    pub fn get_birth_date(&self, id: &Token) -> Option<Date> {
+        self.characters.get(id.as_str()).and_then(Character::birth_date)
+    }
+
+    pub fn get_death_date(&self, id: &Token) -> Option<Date> {
+        self.characters.get(id.as_str()).and_then(Character::death_date)
+    }
+
+    /// Return whether the character has reached adulthood on `date`, if their birth date is known.
+    pub fn is_adult(&self, id: &Token, date: Date) -> Option<bool> {
+        self.get_birth_date(id).map(|birth| birth.add_years(ADULT_AGE) <= date)
+    }
+
     pub fn get_house(&self, id: &Token, date: Date) -> Option<&Token> {
         self.characters.get(id.as_str()).and_then(|ch| ch.get_house(date))
     }
@@ -149,10 +173,16 @@ impl Characters {
         self.characters.get(id.as_str()).and_then(|ch| ch.get_faith(date))
     }
 
+    pub fn get_dna(&self, id: &Token) -> Option<&Token> {
+        self.characters.get(id.as_str()).and_then(|ch| ch.block.get_field_value("dna"))
+    }
+
     pub fn validate(&self, data: &Everything) {
         for item in self.characters.values() {
             if item.born_by(self.config_only_born) {
                 item.validate(data);
+                self.validate_parents(item, data);
+                self.validate_spouses(item);
             }
         }
         for item in &self.duplicates {
@@ -162,9 +192,159 @@ impl Characters {
         }
     }
 
+    /// Check that `item`'s parents could have had them as a child, and that `item`'s dynasty
+    /// follows from theirs.
+    fn validate_parents(&self, item: &Character, data: &Everything) {
+        let birth = item.birth_date();
+        for field in ["father", "mother"] {
+            let Some(token) = item.block.get_field_value(field) else {
+                continue;
+            };
+            let Some(parent) = self.characters.get(token.as_str()) else {
+                continue;
+            };
+            let Some(birth) = birth else {
+                continue;
+            };
+            if let Some(parent_birth) = parent.birth_date() {
+                if birth < parent_birth.add_years(MIN_PARENT_AGE) {
+                    let msg = format!(
+                        "{field} {token} was born on {parent_birth}, less than {MIN_PARENT_AGE} years before {} on {birth}",
+                        item.key
+                    );
+                    warn(ErrorKey::History).msg(msg).loc(token).loc(&parent.key, field).push();
+                } else if field == "mother" && birth > parent_birth.add_years(MAX_MOTHER_AGE) {
+                    let msg = format!(
+                        "mother {token} was born on {parent_birth}, more than {MAX_MOTHER_AGE} years before {} on {birth}",
+                        item.key
+                    );
+                    warn(ErrorKey::History).msg(msg).loc(token).loc(&parent.key, field).push();
+                }
+            }
+            if let Some(parent_death) = parent.death_date() {
+                // A father only has to be alive at conception
+                let (event, limit) = if field == "father" {
+                    ("conceived", birth.add_months(-9))
+                } else {
+                    ("born", birth)
+                };
+                if parent_death < limit {
+                    let msg = format!(
+                        "{field} {token} died on {parent_death}, before {} was {event}",
+                        item.key
+                    );
+                    warn(ErrorKey::History).msg(msg).loc(token).loc(&parent.key, field).push();
+                }
+            }
+        }
+
+        let Some(birth) = birth else {
+            return;
+        };
+        // `dynasty` and `dynasty_house` must agree with each other
+        if let (Some(dynasty), Some(house)) = (item.get_dynasty(birth), item.get_house(birth)) {
+            if let Some(house_dynasty) = House::get_dynasty(house.as_str(), data) {
+                if house_dynasty != dynasty {
+                    let msg =
+                        format!("house {house} belongs to dynasty {house_dynasty}, not {dynasty}");
+                    warn(ErrorKey::History).msg(msg).loc(house).loc(dynasty, "dynasty").push();
+                }
+            }
+        }
+        // The dynasty should come from the father, or from the mother in a matrilineal marriage
+        let Some(father) = item.block.get_field_value("father") else {
+            return;
+        };
+        let Some(father_dynasty) = self.get_dynasty(father, birth, data) else {
+            return;
+        };
+        let mother_dynasty = item
+            .block
+            .get_field_value("mother")
+            .and_then(|mother| self.get_dynasty(mother, birth, data));
+        match self.get_dynasty(&item.key, birth, data) {
+            Some(dynasty) => {
+                if dynasty != father_dynasty && mother_dynasty != Some(dynasty) {
+                    let msg =
+                        format!("{} is of dynasty {dynasty}, but neither parent is", item.key);
+                    let info = format!("father {father} is of dynasty {father_dynasty}");
+                    warn(ErrorKey::History).msg(msg).info(info).loc(dynasty).push();
+                }
+            }
+            None => {
+                if !item.has_trait("bastard") {
+                    let msg = format!(
+                        "{} has no dynasty, but father {father} is of dynasty {father_dynasty}",
+                        item.key
+                    );
+                    let info =
+                        "the character will be lowborn; add `dynasty_house` or the `bastard` trait";
+                    warn(ErrorKey::History).weak().msg(msg).info(info).loc(&item.key).push();
+                }
+            }
+        }
+    }
+
+    /// Check that both spouses were adults at the wedding, and that the marriage is recorded with
+    /// the same date in both histories.
+    /// Whether they were alive is already checked by `validate_life` for the character itself and
+    /// by the `add_spouse` validation for the spouse.
+    fn validate_spouses(&self, item: &Character) {
+        for (date, spouse_token) in item.spouses() {
+            let Some(spouse) = self.characters.get(spouse_token.as_str()) else {
+                continue;
+            };
+            let other_side: Vec<_> =
+                spouse.spouses().into_iter().filter(|(_, t)| t.is(item.key.as_str())).collect();
+            // If both histories record the marriage, only report from one side
+            if !other_side.is_empty() && item.key.as_str() > spouse.key.as_str() {
+                continue;
+            }
+
+            for (person, other) in [(item, spouse), (spouse, item)] {
+                if person.birth_date().is_some_and(|birth| date < birth.add_years(ADULT_AGE)) {
+                    let msg = format!(
+                        "{} was not yet {ADULT_AGE} when marrying {} on {date}",
+                        person.key, other.key
+                    );
+                    warn(ErrorKey::History)
+                        .msg(msg)
+                        .loc(spouse_token)
+                        .loc(&person.key, "character")
+                        .push();
+                }
+            }
+
+            if other_side.is_empty() {
+                let msg = format!(
+                    "marriage of {} and {spouse_token} on {date} is not recorded in {spouse_token}'s history",
+                    item.key
+                );
+                untidy(ErrorKey::History)
+                    .msg(msg)
+                    .loc(spouse_token)
+                    .loc(&spouse.key, "spouse")
+                    .push();
+            }
+            for (other_date, other_token) in other_side {
+                if other_date != date {
+                    let msg = format!(
+                        "marriage of {} and {spouse_token} is on {date} here but on {other_date} in the spouse's history",
+                        item.key
+                    );
+                    warn(ErrorKey::History)
+                        .msg(msg)
+                        .loc(spouse_token)
+                        .loc(other_token, "other side")
+                        .push();
+                }
+            }
+        }
+    }
+
     // Check the ancestors of `ch` to see if `ch` is among them.
     // If a cycle is found, return a `Vec` with the ancestry from `ch` up to `ch`.
-    pub fn _check_ancestors<'a>(
+    fn check_ancestors<'a>(
         &'a self,
         item: &'a Character,
         ch: &str,
@@ -185,12 +365,12 @@ impl Characters {
         let mut cycle_vec = Vec::new();
         if let Some(token) = item.block.get_field_value("father") {
             if let Some(parent) = self.characters.get(token.as_str()) {
-                cycle_vec = self._check_ancestors(parent, ch, checking);
+                cycle_vec = self.check_ancestors(parent, ch, checking);
             }
         }
         if let Some(token) = item.block.get_field_value("mother") {
             if let Some(parent) = self.characters.get(token.as_str()) {
-                cycle_vec = self._check_ancestors(parent, ch, checking);
+                cycle_vec = self.check_ancestors(parent, ch, checking);
             }
         }
         if !cycle_vec.is_empty() && !first {
@@ -241,7 +421,7 @@ impl FileHandler<Block> for Characters {
         // Find loops in the ancestry tree. These will crash the game.
         for item in self.characters.values() {
             let mut checking = FnvHashSet::default();
-            let cycle_vec = self._check_ancestors(item, item.key.as_str(), &mut checking);
+            let cycle_vec = self.check_ancestors(item, item.key.as_str(), &mut checking);
             // TODO: make this a report with a pointer for every character in the cycle
             if !cycle_vec.is_empty() {
                 let msg = "character is their own ancestor";
@@ -299,6 +479,44 @@ impl Character {
             .or_else(|| self.block.get_field_value_at_date("religion", date))
     }
 
+    /// Return the date of the history entry with the given life event, such as `birth`.
+    /// If the event's value is a date, that date is returned instead.
+    fn life_event_date(&self, event: &str) -> Option<Date> {
+        for (key, block) in self.block.iter_definitions() {
+            if let Ok(date) = Date::try_from(key) {
+                if let Some(bv) = block.get_field(event) {
+                    return Some(
+                        bv.get_value().and_then(|v| Date::try_from(v).ok()).unwrap_or(date),
+                    );
+                }
+            }
+        }
+        None
+    }
+
+    fn birth_date(&self) -> Option<Date> {
+        self.life_event_date("birth")
+    }
+
+    fn death_date(&self) -> Option<Date> {
+        self.life_event_date("death")
+    }
+
+    /// Return the spouses added in this character's history, with the dates of the weddings.
+    fn spouses(&self) -> Vec<(Date, &Token)> {
+        let mut vec = Vec::new();
+        for (key, block) in self.block.iter_definitions() {
+            if let Ok(date) = Date::try_from(key) {
+                for field in ["add_spouse", "add_matrilineal_spouse", "add_same_sex_spouse"] {
+                    for token in block.get_field_values(field) {
+                        vec.push((date, token));
+                    }
+                }
+            }
+        }
+        vec
+    }
+
     fn validate_life_event(
         date: Date,
         gender: Gender,
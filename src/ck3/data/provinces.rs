@@ -12,6 +12,7 @@ use crate::parse::csv::{parse_csv, read_csv};
 use crate::pdxfile::PdxFile;
 use crate::report::{error, fatal, old_warn, report, untidy, ErrorKey, Severity};
 use crate::token::{Loc, Token};
+use crate::vfs::open_image;
 
 pub type ProvId = u32;
 
@@ -206,7 +207,7 @@ impl FileHandler<FileContent> for Ck3Provinces {
                 }
 
                 "provinces.png" => {
-                    let img = match image::open(entry.fullpath()) {
+                    let img = match open_image(entry.fullpath()) {
                         Ok(img) => img,
                         Err(e) => {
                             let msg = format!("could not read `{}`: {e:#}", entry.path().display());
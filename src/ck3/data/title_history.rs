@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use fnv::FnvHashMap;
+use fnv::{FnvHashMap, FnvHashSet};
 
 use crate::block::Block;
 use crate::ck3::data::titles::Tier;
@@ -11,7 +11,7 @@ use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
 use crate::item::Item;
 use crate::pdxfile::PdxFile;
-use crate::report::{error, error_info, old_warn, warn2, ErrorKey};
+use crate::report::{err, error, error_info, old_warn, warn, warn2, ErrorKey};
 use crate::scopes::Scopes;
 use crate::token::Token;
 use crate::tooltipped::Tooltipped;
@@ -52,9 +52,158 @@ impl TitleHistories {
     pub fn validate(&self, data: &Everything) {
         for item in self.histories.values() {
             item.validate(data);
+            self.validate_simulation(item, data);
+            Self::validate_holder_deaths(item, data);
         }
     }
 
+    /// Return the holder of `title` on `date`, according to the title history.
+    pub fn holder_at(&self, title: &str, date: Date) -> Option<&Token> {
+        self.histories.get(title).and_then(|item| item.holder_at(date))
+    }
+
+    /// Return all the titles held by `holder` on `date`, according to the title history.
+    pub fn titles_held_at<'a>(
+        &'a self,
+        holder: &'a str,
+        date: Date,
+    ) -> impl Iterator<Item = &'a Token> + 'a {
+        self.histories
+            .values()
+            .filter(move |item| item.holder_at(date).is_some_and(|token| token.is(holder)))
+            .map(|item| &item.key)
+    }
+
+    /// Return the liege title of `title` on `date`, according to the title history.
+    pub fn liege_at(&self, title: &str, date: Date) -> Option<&Token> {
+        self.histories.get(title).and_then(|item| item.value_at("liege", date))
+    }
+
+    /// Return the de jure liege title of `title` on `date`. This is the one from the title
+    /// history if it was set there, otherwise the title it's nested in in `common/landed_titles`.
+    pub fn de_jure_liege_at(&self, title: &str, date: Date, data: &Everything) -> Option<String> {
+        if let Some(field) = self
+            .histories
+            .get(title)
+            .and_then(|item| item.block.get_field_at_date("de_jure_liege", date))
+        {
+            return field.get_value().filter(|token| !token.is("0")).map(Token::to_string);
+        }
+        data.titles.get(title).and_then(|title| title.parent.clone())
+    }
+
+    /// Check the history of `item` against the other title histories, at every date where
+    /// `item` changes.
+    fn validate_simulation(&self, item: &TitleHistory, data: &Everything) {
+        for (key, block) in item.block.iter_definitions() {
+            let Ok(date) = Date::try_from(key) else {
+                continue;
+            };
+
+            if let Some(liege) = block.get_field_value("liege").filter(|t| !t.is("0")) {
+                let cycle = Self::find_cycle(item.key.as_str(), liege.as_str(), |title| {
+                    self.liege_at(title, date).map(Token::to_string)
+                });
+                if let Some(cycle) = cycle {
+                    let msg = format!("liege cycle on {date}");
+                    let info = format!("via {}", cycle.join(", "));
+                    err(ErrorKey::Loop).msg(msg).info(info).loc(liege).push();
+                }
+            }
+
+            if block.has_key("holder") || block.has_key("liege") {
+                if let (Some(holder), Some(liege)) =
+                    (item.holder_at(date), item.value_at("liege", date))
+                {
+                    if self.holder_at(liege.as_str(), date) == Some(holder) {
+                        let msg = format!(
+                            "{holder} holds both {} and its liege {liege} on {date}",
+                            item.key
+                        );
+                        let info = "a character can't be their own vassal";
+                        warn(ErrorKey::History).msg(msg).info(info).loc(key).push();
+                    }
+                }
+            }
+
+            if let Some(de_jure) = block.get_field_value("de_jure_liege").filter(|t| !t.is("0")) {
+                let cycle = Self::find_cycle(item.key.as_str(), de_jure.as_str(), |title| {
+                    self.de_jure_liege_at(title, date, data)
+                });
+                if let Some(cycle) = cycle {
+                    let msg = format!("de jure liege cycle on {date}");
+                    let info = format!("via {}", cycle.join(", "));
+                    err(ErrorKey::Loop).msg(msg).info(info).loc(de_jure).push();
+                }
+            }
+
+            for field in ["government", "succession_laws"] {
+                if let Some(token) = block.get_key(field) {
+                    if item.holder_at(date).is_none() {
+                        let msg = format!("{} has no holder on {date}", item.key);
+                        let info = format!("setting `{field}` will not have effect here");
+                        warn(ErrorKey::History).msg(msg).info(info).loc(token).push();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Check that each holder in the history of `item` stays alive until the next holder is set.
+    /// Holders who are already dead when they get the title are reported by `verify_alive`.
+    fn validate_holder_deaths(item: &TitleHistory, data: &Everything) {
+        let mut changes = Vec::new();
+        for (key, block) in item.block.iter_definitions() {
+            if let Ok(date) = Date::try_from(key) {
+                for field in ["holder", "holder_ignore_head_of_faith_requirement"] {
+                    changes.extend(block.get_field_values(field).into_iter().map(|t| (date, t)));
+                }
+            }
+        }
+        changes.sort_by_key(|(date, _)| *date);
+
+        for (i, (date, holder)) in changes.iter().enumerate() {
+            if holder.is("0") {
+                continue;
+            }
+            let Some(death) = data.characters.get_death_date(holder) else {
+                continue;
+            };
+            if death < *date {
+                continue;
+            }
+            let next = changes.get(i + 1).map(|(date, _)| *date);
+            if next.map_or(true, |next| death < next) {
+                let msg = format!("{holder} dies on {death} while still holding {}", item.key);
+                let info = match next {
+                    Some(next) => format!("the next holder is only set on {next}"),
+                    None => "no later holder is set".to_string(),
+                };
+                warn(ErrorKey::History).msg(msg).info(info).loc(*holder).push();
+            }
+        }
+    }
+
+    /// Follow the chain of lieges starting at `liege`, using `next` to find each one's liege.
+    /// If the chain leads back to `title`, return the titles in the cycle.
+    fn find_cycle<F>(title: &str, liege: &str, next: F) -> Option<Vec<String>>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut chain = vec![liege.to_string()];
+        let mut seen = FnvHashSet::default();
+        let mut current = liege.to_string();
+        while current != title {
+            // Cycles that don't involve `title` get reported from their own titles
+            if !seen.insert(current.clone()) {
+                return None;
+            }
+            current = next(&current)?;
+            chain.push(current.clone());
+        }
+        Some(chain)
+    }
+
     pub fn verify_has_holder(&self, key: &Token, date: Date, data: &Everything) {
         if let Some(item) = self.histories.get(key.as_str()) {
             item.verify_has_holder(key, date, data);
@@ -102,6 +251,33 @@ impl TitleHistory {
         Self { key, block, tier }
     }
 
+    /// Return the value of `field` as of `date`, if it's set and is not `0`.
+    fn value_at(&self, field: &str, date: Date) -> Option<&Token> {
+        self.block.get_field_value_at_date(field, date).filter(|token| !token.is("0"))
+    }
+
+    /// Return the holder as of `date`. Both `holder` and `holder_ignore_head_of_faith_requirement`
+    /// set the holder, so the latest of either one counts.
+    fn holder_at(&self, date: Date) -> Option<&Token> {
+        let mut found: Option<(Date, &Token)> = None;
+        for (key, block) in self.block.iter_definitions() {
+            let Ok(entry_date) = Date::try_from(key) else {
+                continue;
+            };
+            if entry_date > date {
+                continue;
+            }
+            for field in ["holder", "holder_ignore_head_of_faith_requirement"] {
+                if let Some(token) = block.get_field_value(field) {
+                    if found.map_or(true, |(found_date, _)| found_date <= entry_date) {
+                        found = Some((entry_date, token));
+                    }
+                }
+            }
+        }
+        found.map(|(_, token)| token).filter(|token| !token.is("0"))
+    }
+
     pub fn verify_has_holder(&self, token: &Token, date: Date, data: &Everything) {
         if let Some(holder) = self.block.get_field_at_date("holder", date) {
             // if holder is not a value then we already warned about that
@@ -10,9 +10,10 @@ use strum::IntoEnumIterator;
 use crate::block::{Block, BlockItem, Comparator, Eq::*, Field, BV};
 use crate::helpers::stringify_list;
 use crate::report::{
-    err, error, set_predicate, set_show_loaded_mods, set_show_vanilla, Confidence, ErrorKey,
-    ErrorLoc, FilterRule, PointedMessage, Severity,
+    err, error, set_overrides, set_predicate, set_show_loaded_mods, set_show_vanilla, Confidence,
+    ErrorKey, ErrorLoc, FilterRule, PointedMessage, ReportOverride, Severity,
 };
+use crate::token::Token;
 
 /// Checks for legacy ignore blocks (that no longer work) and report an error if they are present.
 pub fn check_for_legacy_ignore(config: &Block) {
@@ -45,6 +46,71 @@ pub fn load_filter(config: &Block) {
         } else {
             set_predicate(FilterRule::default());
         }
+    } else {
+        set_show_vanilla(false);
+        set_show_loaded_mods(false);
+        set_predicate(FilterRule::default());
+    }
+}
+
+/// Load the `severity_override` and `confidence_override` blocks.
+pub fn load_overrides(config: &Block) {
+    let mut overrides = Vec::new();
+    assert_one_key("severity_override", config);
+    if let Some(block) = config.get_field_block("severity_override") {
+        for field in block.iter_items().filter_map(BlockItem::expect_field) {
+            if let Some((mut rule, token)) = load_override(field) {
+                rule.severity = parse_severity(token);
+                if rule.severity.is_some() {
+                    overrides.push(rule);
+                }
+            }
+        }
+    }
+    assert_one_key("confidence_override", config);
+    if let Some(block) = config.get_field_block("confidence_override") {
+        for field in block.iter_items().filter_map(BlockItem::expect_field) {
+            if let Some((mut rule, token)) = load_override(field) {
+                rule.confidence = parse_confidence(token);
+                if rule.confidence.is_some() {
+                    overrides.push(rule);
+                }
+            }
+        }
+    }
+    set_overrides(overrides);
+}
+
+/// Load one override, either of the form `key = level` or of the form
+/// `key = { value = level file = pattern text = "..." }`.
+/// Return the override without its new level, together with the token that holds the level.
+fn load_override(field: &Field) -> Option<(ReportOverride, &Token)> {
+    let Field(key, _, bv) = field;
+    let error_key = parse_error_key(key)?;
+    let mut rule = ReportOverride::new(error_key);
+    match bv {
+        BV::Value(token) => Some((rule, token)),
+        BV::Block(block) => {
+            assert_one_key("value", block);
+            assert_one_key("text", block);
+            let mut value = None;
+            for Field(key, _, bv) in block.iter_items().filter_map(BlockItem::expect_field) {
+                match (key.as_str(), bv) {
+                    ("value", BV::Value(token)) => value = Some(token),
+                    ("file", BV::Value(token)) => rule.files.push(token.to_string()),
+                    ("text", BV::Value(token)) => rule.text = Some(token.to_string()),
+                    _ => error(
+                        key,
+                        ErrorKey::Config,
+                        "Unexpected key. Example usage: `missing-localization = { value = error file = \"localization/english/**\" }`",
+                    ),
+                }
+            }
+            if value.is_none() {
+                error(block, ErrorKey::Config, "Missing `value`. This override will be ignored.");
+            }
+            Some((rule, value?))
+        }
     }
 }
 
@@ -274,21 +340,23 @@ fn load_rule_severity(comparator: Comparator, value: &BV) -> Option<FilterRule>
             );
             None
         }
-        BV::Value(token) => {
-            if let Ok(severity) = token.as_str().to_lowercase().parse() {
-                Some(FilterRule::Severity(comparator, severity))
-            } else {
-                error(
-                    token,
-                    ErrorKey::Config,
-                    &format!(
-                        "Invalid Severity value. Valid values: {}",
-                        stringify_list(&Severity::iter().map(Severity::into).collect::<Vec<_>>()),
-                    ),
-                );
-                None
-            }
-        }
+        BV::Value(token) => Some(FilterRule::Severity(comparator, parse_severity(token)?)),
+    }
+}
+
+fn parse_severity(token: &Token) -> Option<Severity> {
+    if let Ok(severity) = token.as_str().to_lowercase().parse() {
+        Some(severity)
+    } else {
+        error(
+            token,
+            ErrorKey::Config,
+            &format!(
+                "Invalid Severity value. Valid values: {}",
+                stringify_list(&Severity::iter().map(Severity::into).collect::<Vec<_>>()),
+            ),
+        );
+        None
     }
 }
 
@@ -302,23 +370,23 @@ fn load_rule_confidence(comparator: Comparator, value: &BV) -> Option<FilterRule
             );
             None
         }
-        BV::Value(token) => {
-            if let Ok(confidence) = token.as_str().to_lowercase().parse() {
-                Some(FilterRule::Confidence(comparator, confidence))
-            } else {
-                error(
-                    token,
-                    ErrorKey::Config,
-                    &format!(
-                        "Invalid Confidence value. Valid values are {}",
-                        stringify_list(
-                            &Confidence::iter().map(Confidence::into).collect::<Vec<_>>()
-                        ),
-                    ),
-                );
-                None
-            }
-        }
+        BV::Value(token) => Some(FilterRule::Confidence(comparator, parse_confidence(token)?)),
+    }
+}
+
+fn parse_confidence(token: &Token) -> Option<Confidence> {
+    if let Ok(confidence) = token.as_str().to_lowercase().parse() {
+        Some(confidence)
+    } else {
+        error(
+            token,
+            ErrorKey::Config,
+            &format!(
+                "Invalid Confidence value. Valid values are {}",
+                stringify_list(&Confidence::iter().map(Confidence::into).collect::<Vec<_>>()),
+            ),
+        );
+        None
     }
 }
 
@@ -332,18 +400,20 @@ fn load_rule_key(value: &BV) -> Option<FilterRule> {
             );
             None
         }
-        BV::Value(token) => {
-            if let Ok(error_key) = token.as_str().parse() {
-                Some(FilterRule::Key(error_key))
-            } else {
-                error(
-                    token,
-                    ErrorKey::Config,
-                    "Invalid key. In the output, keys are listed between parentheses on the first line of each report. For example, in `Warning(missing-item)`, the key is `missing-item`.",
-                );
-                None
-            }
-        }
+        BV::Value(token) => Some(FilterRule::Key(parse_error_key(token)?)),
+    }
+}
+
+fn parse_error_key(token: &Token) -> Option<ErrorKey> {
+    if let Ok(error_key) = token.as_str().parse() {
+        Some(error_key)
+    } else {
+        error(
+            token,
+            ErrorKey::Config,
+            "Invalid key. In the output, keys are listed between parentheses on the first line of each report. For example, in `Warning(missing-item)`, the key is `missing-item`.",
+        );
+        None
     }
 }
 
@@ -256,7 +256,7 @@ impl ScopeContext {
     /// Look up a named scope and return its scope types if it's known.
     ///
     /// Callers should probably check [`Self::is_strict()`] as well.
-    pub fn is_name_defined(&mut self, name: &str) -> Option<Scopes> {
+    pub fn is_name_defined(&self, name: &str) -> Option<Scopes> {
         if let Some(&idx) = self.names.get(name) {
             #[allow(clippy::match_on_vec_items)] // invariant guarantees no panic
             Some(match self.named[idx] {
@@ -270,6 +270,20 @@ impl ScopeContext {
         }
     }
 
+    /// Return the names of all the named scopes known in this `ScopeContext`.
+    pub fn scope_names(&self) -> impl Iterator<Item = &str> {
+        self.names.keys().map(String::as_str)
+    }
+
+    /// Return the names of the named scopes that are expected to be set on entry to this
+    /// `ScopeContext`.
+    pub fn input_scope_names(&self) -> impl Iterator<Item = &str> {
+        self.names
+            .iter()
+            .filter(|(_, &idx)| self.is_input[idx].is_some())
+            .map(|(name, _)| name.as_str())
+    }
+
     /// This is called when the script does `exists = scope:name`.
     ///
     /// It records `name` as "known", but with no scope type information, and records that the
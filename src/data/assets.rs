@@ -1,6 +1,7 @@
 use std::path::PathBuf;
+use std::sync::RwLock;
 
-use fnv::FnvHashMap;
+use fnv::{FnvHashMap, FnvHashSet};
 
 use crate::block::{Block, BV};
 use crate::everything::Everything;
@@ -9,19 +10,29 @@ use crate::game::Game;
 use crate::helpers::dup_error;
 use crate::item::Item;
 use crate::pdxfile::PdxFile;
-use crate::report::{old_warn, warn2, Confidence, ErrorKey, Severity};
+use crate::report::{
+    old_warn, warn, warn2, warn_abbreviated, warn_header, will_log_abbreviated, Confidence,
+    ErrorKey, Severity,
+};
 use crate::token::Token;
 use crate::util::SmartJoin;
 use crate::validate::validate_numeric_range;
 use crate::validator::Validator;
 
-#[derive(Clone, Debug, Default)]
+#[derive(Debug, Default)]
 #[allow(clippy::struct_field_names)]
 pub struct Assets {
     assets: FnvHashMap<String, Asset>,
     attributes: FnvHashMap<String, Token>,
     blend_shapes: FnvHashMap<String, Token>,
     textures: FnvHashMap<String, (FileEntry, Token)>,
+    /// The keys of `textures`, keyed by their lowercased form. Built in `finalize`.
+    textures_lowercase: FnvHashMap<String, String>,
+    /// The names of the assets that replaced an asset of the same name from vanilla or from
+    /// another loaded mod.
+    overrides: FnvHashSet<String>,
+    /// The assets that were referred to during validation, as `Asset`, `Entity` or `Pdxmesh`.
+    used: RwLock<FnvHashSet<(Item, String)>>,
 }
 
 impl Assets {
@@ -47,6 +58,8 @@ impl Assets {
             if let Some(other) = self.assets.get(name.as_str()) {
                 if other.key.loc.kind >= name.loc.kind {
                     dup_error(name, &other.key, "asset");
+                } else {
+                    self.overrides.insert(name.to_string());
                 }
             }
             self.assets
@@ -86,6 +99,27 @@ impl Assets {
         self.assets.values().filter(|item| item.key.is("entity")).map(|item| &item.name)
     }
 
+    /// Return the ids of the animations that the pdxmesh `key` has, including the ones it imports
+    /// from other assets. Returns `None` if not all of them can be found.
+    fn mesh_animations(&self, key: &str) -> Option<Vec<&Token>> {
+        let mut seen = FnvHashSet::default();
+        let mut todo = vec![key];
+        let mut animations = Vec::new();
+        while let Some(key) = todo.pop() {
+            if seen.insert(key) {
+                let asset = self.assets.get(key)?;
+                for (key, block) in asset.block.iter_definitions() {
+                    if key.is("animation") || key.is("additive_animation") {
+                        animations.extend(block.get_field_value("id"));
+                    } else if key.is("import") {
+                        todo.push(block.get_field_value("name")?.as_str());
+                    }
+                }
+            }
+        }
+        Some(animations)
+    }
+
     pub fn blend_shape_exists(&self, key: &str) -> bool {
         self.blend_shapes.contains_key(key)
     }
@@ -114,6 +148,51 @@ impl Assets {
         self.textures.get(key).map(|(entry, _)| entry)
     }
 
+    /// Look up a texture the way Windows would, ignoring case.
+    pub fn get_texture_case_insensitive(&self, key: &str) -> Option<&FileEntry> {
+        self.textures_lowercase.get(&key.to_lowercase()).and_then(|key| self.get_texture(key))
+    }
+
+    pub fn mark_used(&self, itype: Item, key: &str) {
+        self.used.write().unwrap().insert((itype, key.to_string()));
+    }
+
+    /// Report the entities and meshes that are never referred to.
+    /// Assets that replace one from vanilla or another loaded mod are not reported, because the
+    /// game itself may be using them.
+    pub fn check_unused(&self) {
+        let used = self.used.read().unwrap();
+        let is_used = |itype: Item, name: &str| {
+            // A reference to an `Asset` can be to either kind
+            self.overrides.contains(name)
+                || used.contains(&(itype, name.to_string()))
+                || used.contains(&(Item::Asset, name.to_string()))
+        };
+        for (kind, itype, header) in [
+            ("entity", Item::Entity, "Unused entities:\n"),
+            ("pdxmesh", Item::Pdxmesh, "Unused meshes:\n"),
+        ] {
+            let mut vec: Vec<_> = self
+                .assets
+                .values()
+                .filter(|item| item.key.is(kind) && !is_used(itype, item.name.as_str()))
+                .map(|item| &item.name)
+                .collect();
+            vec.sort_unstable_by_key(|name| name.loc);
+            let mut printed_header = false;
+            for name in vec {
+                if !printed_header && will_log_abbreviated(name, ErrorKey::UnusedAsset) {
+                    warn_header(ErrorKey::UnusedAsset, header);
+                    printed_header = true;
+                }
+                warn_abbreviated(name, ErrorKey::UnusedAsset);
+            }
+            if printed_header {
+                warn_header(ErrorKey::UnusedAsset, "\n");
+            }
+        }
+    }
+
     pub fn validate(&self, data: &Everything) {
         for item in self.assets.values() {
             item.validate(data);
@@ -163,6 +242,12 @@ impl FileHandler<Option<Block>> for Assets {
             self.load_item(key, block);
         }
     }
+
+    fn finalize(&mut self) {
+        for key in self.textures.keys() {
+            self.textures_lowercase.insert(key.to_lowercase(), key.clone());
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -241,6 +326,21 @@ impl Asset {
     }
 
     pub fn validate_entity(&self, data: &Everything) {
+        let mut states = Vec::new();
+        let mut attachment_ids = Vec::new();
+        for (key, block) in self.block.iter_definitions() {
+            if key.is("state") {
+                states.extend(block.get_field_value("name"));
+                for (key, block) in block.iter_definitions() {
+                    if key.is("start_event") || key.is("event") {
+                        attachment_ids.extend(block.get_field_value("attachment_id"));
+                    }
+                }
+            }
+        }
+        let mesh = self.block.get_field_value("pdxmesh");
+        let animations = mesh.and_then(|mesh| data.assets.mesh_animations(mesh.as_str()));
+
         let mut vd = Validator::new(&self.block, data);
         vd.field_value("name");
         vd.field_item("pdxmesh", Item::Pdxmesh);
@@ -302,13 +402,27 @@ impl Asset {
             vd.field_numeric("state_time");
             vd.field_bool("looping");
             vd.field_numeric("animation_speed");
-            vd.field_value("next_state"); // TODO
+            if let Some(token) = vd.field_value("next_state") {
+                verify_state(token, &states);
+            }
             vd.field("chance"); // TODO: can be integer or block
-            vd.field_value("animation"); // TODO
+            if let Some(token) = vd.field_value("animation") {
+                // Animations can also come from a parent entity, so this is not certain
+                if let (Some(mesh), Some(animations)) = (mesh, &animations) {
+                    if !animations.iter().any(|id| id.is(token.as_str())) {
+                        let msg = format!("animation `{token}` is not defined in pdxmesh `{mesh}`");
+                        warn(ErrorKey::MissingItem).weak().msg(msg).loc(token).push();
+                    }
+                }
+            }
             vd.field_numeric("animation_blend_time");
             vd.field_validated("time_offset", validate_time_offset);
-            vd.multi_field_validated_block("start_event", validate_event);
-            vd.multi_field_validated_block("event", validate_event);
+            vd.multi_field_validated_block("start_event", |block, data| {
+                validate_event(block, data, &attachment_ids);
+            });
+            vd.multi_field_validated_block("event", |block, data| {
+                validate_event(block, data, &attachment_ids);
+            });
             vd.multi_field_validated("propagate_state", |bv, data| {
                 match bv {
                     BV::Value(_token) => (), // TODO
@@ -320,7 +434,9 @@ impl Asset {
                 }
             });
         });
-        vd.field_value("default_state"); // TODO: must be a state name
+        if let Some(token) = vd.field_value("default_state") {
+            verify_state(token, &states);
+        }
         vd.multi_field_validated_block("locator", |block, data| {
             let mut vd = Validator::new(block, data);
             vd.req_field("name");
@@ -376,7 +492,14 @@ impl Asset {
     }
 }
 
-fn validate_event(block: &Block, data: &Everything) {
+fn verify_state(token: &Token, states: &[&Token]) {
+    if !states.iter().any(|state| state.is(token.as_str())) {
+        let msg = format!("state `{token}` is not defined in this entity");
+        warn(ErrorKey::MissingItem).msg(msg).loc(token).push();
+    }
+}
+
+fn validate_event(block: &Block, data: &Everything, attachment_ids: &[&Token]) {
     let mut vd = Validator::new(block, data);
     vd.field_numeric("time");
     vd.field_numeric("life");
@@ -390,8 +513,13 @@ fn validate_event(block: &Block, data: &Everything) {
     vd.field_bool("trigger_once");
     vd.field_bool("use_parent_nodes");
     vd.field_integer("skip_forward");
-    vd.field_value("attachment_id"); // TODO
-    vd.field_value("remove_attachment"); // TODO
+    vd.field_value("attachment_id");
+    if let Some(token) = vd.field_value("remove_attachment") {
+        if !attachment_ids.iter().any(|id| id.is(token.as_str())) {
+            let msg = format!("no event in this entity has attachment_id `{token}`");
+            warn(ErrorKey::MissingItem).weak().msg(msg).loc(token).push();
+        }
+    }
     vd.field_item("entity", Item::Entity);
     vd.multi_field_validated_block("soundparameter", |block, data| {
         let mut vd = Validator::new(block, data);
@@ -427,12 +555,18 @@ fn validate_meshsettings(block: &Block, data: &Everything) {
         vd.field_integer("index");
         vd.field_bool("srgb");
     });
-    vd.field_value("shader"); // TODO
-    if let Some(token) = vd.field_value("shader_file") {
-        data.verify_exists(Item::File, token);
+    vd.field_item("shader_file", Item::File);
+    let shader_file = block.get_field_value("shader_file");
+    for field in &["shader", "shadow_shader"] {
+        if let Some(token) = vd.field_value(field) {
+            if let Some(file) = shader_file {
+                data.shaders.verify_effect_in_file(file, token, data);
+            } else {
+                data.verify_exists(Item::ShaderEffect, token);
+            }
+        }
     }
     vd.field_value("subpass");
-    vd.field_value("shadow_shader");
     if Game::is_vic3() {
         vd.field_list("additional_shader_defines");
     }
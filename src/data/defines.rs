@@ -40,7 +40,6 @@ impl Defines {
         }
     }
 
-    #[cfg(feature = "ck3")] // vic3 happens not to use
     pub fn get_bv(&self, key: &str) -> Option<&BV> {
         self.defines.get(key).map(|d| &d.bv)
     }
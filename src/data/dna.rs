@@ -1,10 +1,11 @@
 //! Character DNA for portraits. Used in CK3 and Vic3 but not Imperator.
 
-use crate::block::Block;
+use crate::block::{Block, BV};
 use crate::db::{Db, DbKind};
 use crate::everything::Everything;
 use crate::game::GameFlags;
 use crate::item::{Item, ItemLoader};
+use crate::report::{err, warn, ErrorKey};
 use crate::token::Token;
 use crate::validator::Validator;
 
@@ -25,7 +26,10 @@ impl DbKind for Dna {
     fn validate(&self, _key: &Token, block: &Block, data: &Everything) {
         let mut vd = Validator::new(block, data);
 
-        vd.field_validated_block("portrait_info", validate_portrait_info);
+        vd.field_validated("portrait_info", |bv, data| match bv {
+            BV::Value(token) => validate_dna_string(token, data),
+            BV::Block(block) => validate_portrait_info(block, data),
+        });
         vd.field_bool("enabled");
     }
 }
@@ -42,3 +46,99 @@ pub fn validate_genes(block: &Block, data: &Everything) {
         data.validate_use(Item::GeneCategory, key, block);
     });
 }
+
+/// Validate a base64 DNA string, as copied from the game.
+///
+/// The decoded string is assumed to have 4 bytes per gene, for all gene categories including the
+/// special genes, in the order the genes are defined. For morph and accessory genes the bytes are
+/// the dominant template index and value followed by the recessive template index and value, just
+/// like in a `genes` block. For color genes they are two pairs of color coordinates.
+///
+/// This layout has not been verified against DNA strings from the game, so the reports are weak.
+/// Only the template indices are checked. The values are single bytes covering the whole 0 to 255
+/// range of a gene value, so every byte is a valid value.
+pub fn validate_dna_string(token: &Token, data: &Everything) {
+    let Some(bytes) = decode_base64(token.as_str()) else {
+        let msg = "could not decode DNA string";
+        let info = "expected a base64 string";
+        err(ErrorKey::Validation).weak().msg(msg).info(info).loc(token).push();
+        return;
+    };
+
+    let genes = gene_layout(data);
+    if bytes.len() != genes.len() * 4 {
+        let msg = format!(
+            "DNA string has {} bytes, but the {} defined genes need {}",
+            bytes.len(),
+            genes.len(),
+            genes.len() * 4
+        );
+        let info =
+            "it was probably made for an older set of genes, before an update or DLC added some";
+        warn(ErrorKey::Validation).weak().msg(msg).info(info).loc(token).push();
+        return;
+    }
+
+    for ((key, templates), chunk) in genes.iter().zip(bytes.chunks_exact(4)) {
+        let Some(templates) = templates else {
+            continue;
+        };
+        for index in [chunk[0], chunk[2]] {
+            if !templates.contains(&i64::from(index)) {
+                let msg = format!("DNA string uses template index {index} of gene {key}");
+                let info = format!("gene {key} has no template with that index");
+                warn(ErrorKey::MissingItem).weak().msg(msg).info(info).loc(token).push();
+            }
+        }
+    }
+}
+
+/// Return the gene categories in the order they are stored in DNA strings, together with the
+/// template indices of each gene. Color genes have no templates and get `None`.
+fn gene_layout(data: &Everything) -> Vec<(&Token, Option<Vec<i64>>)> {
+    let mut genes: Vec<_> = data
+        .database
+        .iter_key_block(Item::GeneCategory)
+        .map(|(key, block)| {
+            let templates = if block.has_key("color") {
+                None
+            } else {
+                Some(
+                    block
+                        .iter_definitions()
+                        .filter_map(|(_, block)| block.get_field_integer("index"))
+                        .collect(),
+                )
+            };
+            (key, templates)
+        })
+        .collect();
+    // The game loads the gene files in filename order
+    genes.sort_by_key(|(key, _)| (key.loc.filename(), key.loc.line, key.loc.column));
+    genes
+}
+
+/// Decode a standard base64 string, with or without padding.
+fn decode_base64(s: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for c in s.trim_end_matches('=').bytes() {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        acc = (acc << 6) | u32::from(v);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push(u8::try_from(acc >> bits).ok()?);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
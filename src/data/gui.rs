@@ -4,18 +4,21 @@ use std::mem::drop;
 use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 
-use fnv::FnvHashMap;
+use fnv::{FnvHashMap, FnvHashSet};
 
 use crate::block::{Block, BlockItem, Field, BV};
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
-use crate::gui::{BuiltinWidget, GuiBlock, GuiBlockFrom};
+use crate::gui::{
+    BuiltinWidget, GuiBlock, GuiBlockFrom, GuiContext, GuiValidation, WidgetProperty,
+};
 use crate::helpers::dup_error;
 use crate::item::Item;
 use crate::lowercase::Lowercase;
 use crate::pdxfile::PdxFile;
 use crate::report::{
-    err, error, error_info, fatal, old_warn, untidy, warn, warn_info, ErrorKey, Severity,
+    err, error, error_info, fatal, old_warn, untidy, warn, warn_abbreviated, warn_header,
+    warn_info, will_log_abbreviated, ErrorKey, Severity,
 };
 use crate::token::Token;
 use crate::validator::Validator;
@@ -33,6 +36,13 @@ pub struct Gui {
     textformats: FnvHashMap<String, TextFormat>,
     // This is indexed by a (colorblindmode, textformatname) pair
     textformats_colorblind: FnvHashMap<(String, String), TextFormat>,
+    /// The items that replaced an item of the same name from vanilla or from another loaded mod.
+    /// Type names are lowercased here too.
+    overrides: FnvHashSet<(Item, String)>,
+    /// The layers, textformats and texticons that were referred to during validation.
+    /// Types and templates are not tracked here, because they are found by following the
+    /// references from the top-level widgets instead.
+    used: RwLock<FnvHashSet<(Item, String)>>,
 }
 
 impl Gui {
@@ -137,6 +147,8 @@ impl Gui {
         if let Some(other) = self.types.get(&key_lc) {
             if other.key.loc.kind >= key.loc.kind {
                 dup_error(&key, &other.key, "gui type");
+            } else {
+                self.overrides.insert((Item::GuiType, key_lc.clone()));
             }
         }
         self.types.insert(key_lc, GuiType::new(key, base, block));
@@ -146,6 +158,8 @@ impl Gui {
         if let Some(other) = self.templates.get(key.as_str()) {
             if other.key.loc.kind >= key.loc.kind {
                 dup_error(&key, &other.key, "gui template");
+            } else {
+                self.overrides.insert((Item::GuiTemplate, key.to_string()));
             }
         }
         self.templates.insert(key.to_string(), GuiTemplate::new(key, block));
@@ -155,6 +169,8 @@ impl Gui {
         if let Some(other) = self.layers.get(key.as_str()) {
             if other.key.loc.kind >= key.loc.kind {
                 dup_error(&key, &other.key, "gui layer");
+            } else {
+                self.overrides.insert((Item::GuiLayer, key.to_string()));
             }
         }
         self.layers.insert(key.to_string(), GuiLayer::new(key, block));
@@ -183,6 +199,8 @@ impl Gui {
             if let Some(other) = self.textformats.get(key.as_str()) {
                 if other.key.loc.kind >= key.loc.kind {
                     dup_error(&key, &other.key, "textformat");
+                } else {
+                    self.overrides.insert((Item::TextFormat, key.to_string()));
                 }
             }
             self.textformats.insert(key.to_string(), TextFormat::new(key, block, None));
@@ -229,6 +247,119 @@ impl Gui {
         self.textformats.values().map(|item| &item.key)
     }
 
+    /// Describe the fully resolved widget tree of the window or widget named `name`, or else of
+    /// the type `name`. Returns `None` if there is no such widget or type.
+    pub fn widget_tree(&self, name: &str) -> Option<String> {
+        let mut out = String::new();
+        let mut paths: Vec<_> = self.files.keys().collect();
+        paths.sort();
+        for path in paths {
+            for widget in &self.files[path] {
+                if let Some((key, found)) = widget.gui_block().find_named(widget.key.as_str(), name)
+                {
+                    found.dump_as(&format!("{key} ="), 0, &mut out);
+                    return Some(out);
+                }
+            }
+        }
+        let guitype = self.types.get(&name.to_lowercase())?;
+        let header = format!("type {} = {}", guitype.key, guitype.base);
+        guitype.gui_block(&self.types, &self.templates).dump_as(&header, 0, &mut out);
+        Some(out)
+    }
+
+    pub fn mark_used(&self, itype: Item, key: &str) {
+        self.used.write().unwrap().insert((itype, key.to_string()));
+    }
+
+    /// Find the types and templates that are reachable from the widgets at the top level of the
+    /// gui files, through their base types, contained widgets, `using` and template properties.
+    /// Returns the lowercased type names and the template names.
+    fn reachable(&self) -> (FnvHashSet<String>, FnvHashSet<String>) {
+        let mut types = FnvHashSet::default();
+        let mut templates = FnvHashSet::default();
+        let mut todo = Vec::new();
+        for widgets in self.files.values() {
+            for widget in widgets {
+                todo.push((Item::GuiType, widget.key.as_str().to_lowercase()));
+                self.collect_references(&widget.block, &mut todo);
+            }
+        }
+        while let Some((itype, name)) = todo.pop() {
+            if itype == Item::GuiType {
+                if let Some(guitype) = self.types.get(&name) {
+                    if types.insert(name) {
+                        todo.push((Item::GuiType, guitype.base.as_str().to_lowercase()));
+                        self.collect_references(&guitype.block, &mut todo);
+                    }
+                }
+            } else if let Some(template) = self.templates.get(&name) {
+                if templates.insert(name) {
+                    self.collect_references(&template.block, &mut todo);
+                }
+            }
+        }
+        (types, templates)
+    }
+
+    /// Add the types and templates referred to directly from `block` to `todo`.
+    fn collect_references(&self, block: &Block, todo: &mut Vec<(Item, String)>) {
+        for item in block.iter_items() {
+            match item {
+                BlockItem::Field(Field(key, _, BV::Value(value))) => {
+                    let key_lc = Lowercase::new(key.as_str());
+                    if key_lc == "using"
+                        || WidgetProperty::try_from(&key_lc).is_ok_and(|prop| {
+                            GuiValidation::from_property(prop) == GuiValidation::Widget
+                        })
+                    {
+                        todo.push((Item::GuiTemplate, value.to_string()));
+                    }
+                }
+                BlockItem::Field(Field(key, _, BV::Block(block))) => {
+                    let key_lc = key.as_str().to_lowercase();
+                    if self.types.contains_key(&key_lc) {
+                        todo.push((Item::GuiType, key_lc));
+                    }
+                    self.collect_references(block, todo);
+                }
+                BlockItem::Block(block) => self.collect_references(block, todo),
+                BlockItem::Value(_) => (),
+            }
+        }
+    }
+
+    /// Report the types, templates, layers, textformats and texticons that are never used.
+    /// Items that replace one from vanilla or another loaded mod are not reported, because the
+    /// game itself may be using them.
+    pub fn check_unused(&self) {
+        let (used_types, used_templates) = self.reachable();
+        let used = self.used.read().unwrap();
+        let is_unused = |itype: Item, key: &str| {
+            !self.overrides.contains(&(itype, key.to_string()))
+                && match itype {
+                    Item::GuiType => !used_types.contains(key),
+                    Item::GuiTemplate => !used_templates.contains(key),
+                    _ => !used.contains(&(itype, key.to_string())),
+                }
+        };
+
+        let types = self.types.iter().filter(|(key, _)| is_unused(Item::GuiType, key));
+        report_unused("Unused gui types:\n", types.map(|(_, item)| &item.key).collect());
+        let templates = self.templates.iter().filter(|(key, _)| is_unused(Item::GuiTemplate, key));
+        report_unused("Unused gui templates:\n", templates.map(|(_, item)| &item.key).collect());
+        let layers = self.layers.iter().filter(|(key, _)| is_unused(Item::GuiLayer, key));
+        report_unused("Unused gui layers:\n", layers.map(|(_, item)| &item.key).collect());
+        let textformats =
+            self.textformats.iter().filter(|(key, _)| is_unused(Item::TextFormat, key));
+        report_unused("Unused textformats:\n", textformats.map(|(_, item)| &item.key).collect());
+        let texticons = self.texticons.iter().filter(|(key, _)| is_unused(Item::TextIcon, key));
+        report_unused(
+            "Unused texticons:\n",
+            texticons.filter_map(|(_, vec)| vec.first().map(|item| &item.key)).collect(),
+        );
+    }
+
     pub fn validate(&self, data: &Everything) {
         for items in self.files.values() {
             for item in items {
@@ -251,6 +382,9 @@ impl Gui {
         }
         for item in self.textformats.values() {
             item.validate(data);
+            if !self.overrides.contains(&(Item::TextFormat, item.key.to_string())) {
+                item.validate_override();
+            }
         }
         for item in self.textformats_colorblind.values() {
             item.validate(data);
@@ -393,6 +527,26 @@ impl FileHandler<Block> for Gui {
         for item in self.templates.values() {
             _ = item.gui_block(&self.types, &self.templates);
         }
+        for widgets in self.files.values_mut() {
+            for item in widgets {
+                item.calculate_gui_block(&self.types, &self.templates);
+            }
+        }
+    }
+}
+
+fn report_unused(header: &str, mut vec: Vec<&Token>) {
+    vec.sort_unstable_by_key(|key| key.loc);
+    let mut printed_header = false;
+    for key in vec {
+        if !printed_header && will_log_abbreviated(key, ErrorKey::UnusedGui) {
+            warn_header(ErrorKey::UnusedGui, header);
+            printed_header = true;
+        }
+        warn_abbreviated(key, ErrorKey::UnusedGui);
+    }
+    if printed_header {
+        warn_header(ErrorKey::UnusedGui, "\n");
     }
 }
 
@@ -400,28 +554,36 @@ impl FileHandler<Block> for Gui {
 struct GuiWidget {
     key: Token,
     block: Block,
+    gui_block: Option<Arc<GuiBlock>>,
 }
 
 impl GuiWidget {
     pub fn new(key: Token, block: Block) -> Self {
-        Self { key, block }
+        Self { key, block, gui_block: None }
+    }
+
+    pub fn calculate_gui_block(
+        &mut self,
+        types: &FnvHashMap<String, GuiType>,
+        templates: &FnvHashMap<String, GuiTemplate>,
+    ) {
+        let from = GuiBlockFrom::WidgetKey(&self.key);
+        self.gui_block = Some(GuiBlock::from_block(from, &self.block, types, templates));
+    }
+
+    pub fn gui_block(&self) -> &GuiBlock {
+        // unwrapping the Option is safe because they were all calculated during finalize
+        self.gui_block.as_ref().unwrap()
     }
 
     pub fn validate(&self, data: &Everything) {
         data.verify_exists(Item::GuiType, &self.key);
-        let guiblock = GuiBlock::from_block(
-            GuiBlockFrom::WidgetKey(&self.key),
-            &self.block,
-            &data.gui.types,
-            &data.gui.templates,
-        );
-        guiblock.validate(None, data);
+        self.gui_block().validate(None, &GuiContext::default(), data);
     }
 }
 
 #[derive(Clone, Debug)]
 struct TextIcon {
-    #[allow(dead_code)]
     key: Token,
     block: Block,
 }
@@ -449,7 +611,6 @@ impl TextIcon {
 
 #[derive(Clone, Debug)]
 struct TextFormat {
-    #[allow(dead_code)]
     key: Token,
     block: Block,
     color_blind_mode: Option<Token>,
@@ -471,6 +632,15 @@ impl TextFormat {
         vd.field_bool("override");
         vd.field_value("format"); // TODO
     }
+
+    /// Warn about an `override = yes` textformat that did not replace anything.
+    pub fn validate_override(&self) {
+        if self.color_blind_mode.is_none() && self.block.field_value_is("override", "yes") {
+            let msg = format!("textformat `{}` is an override, but there is no textformat of that name to override", self.key);
+            let info = "the original may have been removed in a game update";
+            untidy(ErrorKey::Unneeded).msg(msg).info(info).loc(&self.key).push();
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -488,7 +658,11 @@ impl GuiTemplate {
 
     pub fn validate(&self, data: &Everything) {
         // unwrapping the Option is safe because they were all calculated during finalize
-        self.gui_block.read().unwrap().as_ref().unwrap().validate(None, data);
+        self.gui_block.read().unwrap().as_ref().unwrap().validate(
+            None,
+            &GuiContext::default(),
+            data,
+        );
     }
 
     pub fn calculate_gui_block(
@@ -569,7 +743,11 @@ impl GuiType {
                 .push();
         }
         // Unwrapping the Option is safe because they were all calculated during finalize
-        self.gui_block.read().unwrap().as_ref().unwrap().validate(None, data);
+        self.gui_block.read().unwrap().as_ref().unwrap().validate(
+            None,
+            &GuiContext::default(),
+            data,
+        );
     }
 
     pub fn calculate_builtin(&self, types: &FnvHashMap<String, GuiType>) -> Option<BuiltinWidget> {
@@ -1,7 +1,9 @@
 //! Validate `.yml` localization files
 
+use std::collections::BTreeSet;
 use std::ffi::OsStr;
-use std::fs::read_to_string;
+use std::fmt::Write;
+use std::fs::{create_dir_all, write};
 use std::num::NonZeroU32;
 use std::path::{Path, PathBuf};
 use std::sync::RwLock;
@@ -11,7 +13,7 @@ use rayon::scope;
 
 use crate::block::Block;
 use crate::context::ScopeContext;
-use crate::datatype::{validate_datatypes, CodeChain, Datatype};
+use crate::datatype::{validate_datatypes, CodeArg, CodeChain, Datatype};
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler, FileKind};
 #[cfg(any(feature = "ck3", feature = "vic3"))]
@@ -23,11 +25,12 @@ use crate::parse::localization::{parse_loca, ValueParser};
 #[cfg(feature = "ck3")]
 use crate::report::warn2;
 use crate::report::{
-    err, error_info, report, warn, warn_abbreviated, warn_header, warn_info, will_maybe_log,
+    err, error_info, report, warn, warn_abbreviated, warn_header, warn_info, will_log_abbreviated,
     ErrorKey, Severity,
 };
 use crate::scopes::Scopes;
-use crate::token::Token;
+use crate::token::{Loc, Token};
+use crate::vfs::read_to_string;
 
 /// Database of all loaded localization keys and their values, for all supported languages.
 #[derive(Debug)]
@@ -149,11 +152,20 @@ pub struct LocaEntry {
     /// The original unparsed value, with enclosing `"` stripped.
     /// This is used for macro replacement.
     orig: Option<Token>,
+    /// The number after the `:`. Translators use it to track which version of the english text
+    /// they translated.
+    version: u32,
 }
 
 impl LocaEntry {
     pub fn new(key: Token, value: LocaValue, orig: Option<Token>) -> Self {
-        Self { key, value, orig }
+        Self { key, value, orig, version: 0 }
+    }
+
+    #[must_use]
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
     }
 
     // returns false to abort expansion in case of an error
@@ -227,6 +239,26 @@ pub enum LocaValue {
     Error,
 }
 
+/// The `#markup` keys that are built into the engine rather than defined as textformats.
+const BUILTIN_MARKUP: &[&str] = &["bold", "italic", "underline", "tooltippable"];
+
+/// Return the textformat names used in a `#markup` token.
+/// Those are the `;`-separated parts without a `:` value that are not builtin markup keys.
+pub fn markup_formats(markup: &Token) -> Vec<Token> {
+    let Some(markup) = markup.strip_prefix("#") else {
+        return Vec::new();
+    };
+    markup
+        .split(';')
+        .into_iter()
+        .filter(|part| {
+            !part.as_str().is_empty()
+                && !part.as_str().contains(':')
+                && !BUILTIN_MARKUP.contains(&part.as_str().to_lowercase().as_str())
+        })
+        .collect()
+}
+
 #[derive(Clone, Debug)]
 pub enum MacroValue {
     Text(Token),
@@ -234,6 +266,92 @@ pub enum MacroValue {
     Keyword(Token, Option<Token>),
 }
 
+/// The parts of a localization value that should be the same in every translation of it.
+#[derive(Debug, Default)]
+struct Placeholders {
+    macros: BTreeSet<String>,
+    code: BTreeSet<String>,
+    markup: BTreeSet<String>,
+    icons: BTreeSet<String>,
+}
+
+impl Placeholders {
+    /// `macros` are the `$KEY$` names the entry had before macro expansion, if any.
+    fn new(entry: &LocaEntry, macros: Option<&BTreeSet<String>>) -> Self {
+        let mut placeholders = Self::default();
+        if let Some(macros) = macros {
+            placeholders.macros.clone_from(macros);
+        }
+        placeholders.add_value(&entry.value);
+        placeholders
+    }
+
+    fn add_value(&mut self, value: &LocaValue) {
+        // Parts that were substituted in from other keys by macro expansion are skipped here,
+        // because those keys get compared on their own.
+        match value {
+            LocaValue::Concat(v) => {
+                for value in v {
+                    self.add_value(value);
+                }
+            }
+            LocaValue::Code(chain, _)
+                if chain.codes.first().is_some_and(|code| code.name.loc.link_idx.is_none()) =>
+            {
+                self.code.insert(format!("[{}]", code_shape(chain)));
+            }
+            LocaValue::Markup(token) if token.loc.link_idx.is_none() => {
+                self.markup.insert(token.to_string());
+            }
+            LocaValue::Icon(token) if token.loc.link_idx.is_none() => {
+                self.icons.insert(format!("@{token}!"));
+            }
+            _ => (),
+        }
+    }
+
+    /// Forget everything except the macros. This is used when a value could not be expanded,
+    /// because its other parts are then unknown.
+    fn only_macros(&mut self) {
+        self.code.clear();
+        self.markup.clear();
+        self.icons.clear();
+    }
+
+    /// Return the parts of `self` that are missing from `other`.
+    fn missing_from<'a>(&'a self, other: &'a Self) -> Vec<&'a str> {
+        let mut vec = Vec::new();
+        vec.extend(self.macros.difference(&other.macros).map(String::as_str));
+        vec.extend(self.code.difference(&other.code).map(String::as_str));
+        vec.extend(self.markup.difference(&other.markup).map(String::as_str));
+        vec.extend(self.icons.difference(&other.icons).map(String::as_str));
+        vec
+    }
+}
+
+/// Render a code chain for comparison between languages.
+/// Literal arguments are left out because some of them, such as the second argument of
+/// `Concept`, are meant to be translated.
+fn code_shape(chain: &CodeChain) -> String {
+    let mut parts = Vec::new();
+    for code in &chain.codes {
+        if code.arguments.is_empty() {
+            parts.push(code.name.to_string());
+        } else {
+            let args: Vec<String> = code
+                .arguments
+                .iter()
+                .map(|arg| match arg {
+                    CodeArg::Chain(chain) => code_shape(chain),
+                    CodeArg::Literal(_) => "'...'".to_string(),
+                })
+                .collect();
+            parts.push(format!("{}({})", code.name, args.join(",")));
+        }
+    }
+    parts.join(".")
+}
+
 fn get_file_lang(filename: &OsStr) -> Option<&'static str> {
     // Deliberate discrepancy here between the check and the error msg below.
     // `l_{}` anywhere in the filename works, but `_l_{}.yml` is still recommended.
@@ -454,6 +572,12 @@ impl Localization {
             LocaValue::Icon(token) => {
                 data.verify_exists(Item::TextIcon, token);
             }
+            // TODO: verify the textformats too, once the builtin markup keys are known
+            LocaValue::Markup(token) => {
+                for format in markup_formats(token) {
+                    data.mark_used(Item::TextFormat, format.as_str());
+                }
+            }
             _ => (),
         }
     }
@@ -556,7 +680,9 @@ impl Localization {
                 vec.sort_unstable_by_key(|entry| &entry.key.loc);
                 let mut printed_header = false;
                 for entry in vec {
-                    if !printed_header && will_maybe_log(&entry.key, ErrorKey::UnusedLocalization) {
+                    if !printed_header
+                        && will_log_abbreviated(&entry.key, ErrorKey::UnusedLocalization)
+                    {
                         warn_header(
                             ErrorKey::UnusedLocalization,
                             &format!("Unused localization - {lang}:\n"),
@@ -572,6 +698,232 @@ impl Localization {
         }
     }
 
+    /// Warn about characters in the mod's localization that none of the fonts configured for
+    /// that language can display. The game shows those as empty boxes.
+    pub fn check_glyphs(&self, data: &Everything) {
+        for &lang in &self.mod_langs {
+            let mut glyphs = FnvHashSet::default();
+            for (_, block) in data.database.iter_key_block(Item::Fontfiles) {
+                for group in block.get_field_blocks("group") {
+                    let languages = group.get_field_list("languages").unwrap_or_default();
+                    // Font language lists may or may not use the `l_` prefix
+                    if !languages.is_empty()
+                        && !languages.iter().any(|l| l.as_str().trim_start_matches("l_") == lang)
+                    {
+                        continue;
+                    }
+                    for file in group.get_field_list("files").unwrap_or_default() {
+                        if let Some(font_glyphs) = data.fonts.glyphs(file.as_str()) {
+                            glyphs.extend(font_glyphs);
+                        }
+                    }
+                }
+            }
+            // Without any font files (such as when there's no vanilla directory), there's
+            // nothing to check against.
+            if glyphs.is_empty() {
+                continue;
+            }
+
+            if let Some(hash) = self.locas.get(lang) {
+                for entry in hash.values() {
+                    if entry.key.loc.kind != FileKind::Mod {
+                        continue;
+                    }
+                    let mut missing = Vec::new();
+                    Self::missing_glyphs(&entry.value, &glyphs, &mut missing);
+                    if let Some(&(_, loc)) = missing.first() {
+                        let chars: Vec<String> =
+                            missing.iter().map(|(c, _)| format!("`{c}`")).collect();
+                        let chars: Vec<&str> = chars.iter().map(String::as_str).collect();
+                        let msg = if chars.len() == 1 {
+                            format!("character {} is not in the {lang} fonts", chars[0])
+                        } else {
+                            format!(
+                                "characters {} are not in the {lang} fonts",
+                                stringify_list(&chars)
+                            )
+                        };
+                        let info = "the game will show them as empty boxes";
+                        warn(ErrorKey::MissingGlyph).msg(msg).info(info).loc(loc).push();
+                    }
+                }
+            }
+        }
+    }
+
+    fn missing_glyphs(
+        value: &LocaValue,
+        glyphs: &FnvHashSet<char>,
+        missing: &mut Vec<(char, Loc)>,
+    ) {
+        match value {
+            LocaValue::Concat(v) => {
+                for value in v {
+                    Self::missing_glyphs(value, glyphs, missing);
+                }
+            }
+            // Text substituted in from other keys gets checked with those keys
+            LocaValue::Text(token) if token.loc.link_idx.is_none() => {
+                let mut loc = token.loc;
+                for c in token.as_str().chars() {
+                    if !c.is_whitespace()
+                        && !c.is_control()
+                        && !glyphs.contains(&c)
+                        && !missing.iter().any(|(m, _)| *m == c)
+                    {
+                        missing.push((c, loc));
+                    }
+                    loc.column += 1;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Return the mod's english entries that are missing from `lang`, and the ones that `lang` has
+    /// with a lower version number than the english entry. Both lists are sorted by location.
+    fn untranslated(&self, lang: &'static str) -> (Vec<&LocaEntry>, Vec<&LocaEntry>) {
+        let mut missing = Vec::new();
+        let mut stale = Vec::new();
+        if let Some(english) = self.locas.get("english") {
+            let translated = self.locas.get(lang);
+            for entry in english.values() {
+                if entry.key.loc.kind != FileKind::Mod {
+                    continue;
+                }
+                match translated.and_then(|hash| hash.get(entry.key.as_str())) {
+                    None => missing.push(entry),
+                    Some(other) if other.version < entry.version => stale.push(entry),
+                    Some(_) => (),
+                }
+            }
+        }
+        missing.sort_unstable_by_key(|entry| &entry.key.loc);
+        stale.sort_unstable_by_key(|entry| &entry.key.loc);
+        (missing, stale)
+    }
+
+    /// Summarize, for each checked language, how many of the mod's english localization keys
+    /// are translated, untranslated, or stale.
+    ///
+    /// A translation is stale if its version number (the number after the `:`) is lower than
+    /// that of the english entry.
+    pub fn coverage_report(&self) -> String {
+        let total = self.locas.get("english").map_or(0, |english| {
+            english.values().filter(|entry| entry.key.loc.kind == FileKind::Mod).count()
+        });
+        let mut report = format!("Localization coverage of {total} english keys:\n");
+        for &lang in &self.check_langs {
+            if lang == "english" {
+                continue;
+            }
+            let (missing, stale) = self.untranslated(lang);
+            let translated = total - missing.len() - stale.len();
+            _ = writeln!(
+                report,
+                "  {lang}: {translated} translated, {} untranslated, {} stale",
+                missing.len(),
+                stale.len()
+            );
+        }
+        report
+    }
+
+    /// Write a stub localization file for each checked language that is missing some of the
+    /// mod's english keys. The stubs contain the english text, marked with a `#NT!` comment,
+    /// and are written to `dir/<language>/untranslated_l_<language>.yml`.
+    ///
+    /// Returns the paths of the files written.
+    pub fn write_stubs(&self, dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+        let mut written = Vec::new();
+        for &lang in &self.check_langs {
+            if lang == "english" {
+                continue;
+            }
+            let (missing, _) = self.untranslated(lang);
+            if missing.is_empty() {
+                continue;
+            }
+            let mut content = format!("\u{feff}l_{lang}:\n");
+            let mut prev_path = None;
+            for entry in missing {
+                let Some(orig) = &entry.orig else {
+                    continue;
+                };
+                let path = entry.key.loc.pathname();
+                if prev_path != Some(path) {
+                    _ = writeln!(content, "\n # from {}", path.display());
+                    prev_path = Some(path);
+                }
+                _ = writeln!(content, " {}:{} \"{orig}\" #NT!", entry.key, entry.version);
+            }
+            let path = dir.join(lang).join(format!("untranslated_l_{lang}.yml"));
+            create_dir_all(dir.join(lang))?;
+            write(&path, content)?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+
+    /// Compare the `$KEY$` macros, `[code]`, `#markup` and `@icon!` parts of every translation
+    /// against the english version of the same key, and warn about any that were dropped or added.
+    fn check_translations(&self, macros: &FnvHashMap<(&'static str, String), BTreeSet<String>>) {
+        let Some(english) = self.locas.get("english") else {
+            return;
+        };
+        for (&lang, hash) in &self.locas {
+            if lang == "english" {
+                continue;
+            }
+            for entry in hash.values() {
+                if entry.key.loc.kind <= FileKind::Vanilla
+                    || matches!(entry.value, LocaValue::Error)
+                {
+                    continue;
+                }
+                let Some(eng_entry) = english.get(entry.key.as_str()) else {
+                    continue;
+                };
+                if matches!(eng_entry.value, LocaValue::Error) {
+                    continue;
+                }
+                let key = entry.key.to_string();
+                let mut ours = Placeholders::new(entry, macros.get(&(lang, key.clone())));
+                let mut theirs = Placeholders::new(eng_entry, macros.get(&("english", key)));
+                if matches!(entry.value, LocaValue::Macro(_))
+                    || matches!(eng_entry.value, LocaValue::Macro(_))
+                {
+                    ours.only_macros();
+                    theirs.only_macros();
+                }
+                let missing = theirs.missing_from(&ours);
+                let added = ours.missing_from(&theirs);
+                if missing.is_empty() && added.is_empty() {
+                    continue;
+                }
+                let quoted = |v: Vec<&str>| {
+                    let v: Vec<String> = v.into_iter().map(|s| format!("`{s}`")).collect();
+                    stringify_list(&v.iter().map(String::as_str).collect::<Vec<_>>())
+                };
+                let mut info = Vec::new();
+                if !missing.is_empty() {
+                    info.push(format!("missing {}", quoted(missing)));
+                }
+                if !added.is_empty() {
+                    info.push(format!("not in english: {}", quoted(added)));
+                }
+                let msg = format!("{lang} localization does not match the english version");
+                warn(ErrorKey::Translation)
+                    .msg(msg)
+                    .info(info.join("; "))
+                    .loc(&entry.key)
+                    .loc(&eng_entry.key, "english version")
+                    .push();
+            }
+        }
+    }
+
     #[cfg(feature = "ck3")]
     pub fn check_pod_loca(&self, data: &Everything) {
         for lang in &self.mod_langs {
@@ -737,6 +1089,23 @@ impl FileHandler<(&'static str, Vec<LocaEntry>)> for Localization {
             }
         }
 
+        // Remember the macro names before expansion, so that translations can be compared later
+        let mut macros = FnvHashMap::default();
+        for (&lang, hash) in &self.locas {
+            for entry in hash.values() {
+                if let LocaValue::Macro(ref v) = entry.value {
+                    let names = v
+                        .iter()
+                        .filter_map(|macrovalue| match macrovalue {
+                            MacroValue::Keyword(k, _) => Some(format!("${k}$")),
+                            MacroValue::Text(_) => None,
+                        })
+                        .collect();
+                    macros.insert((lang, entry.key.to_string()), names);
+                }
+            }
+        }
+
         // Now expand all the macro values we can, and re-parse them after expansion
         for lang in self.locas.values_mut() {
             let orig_lang = lang.clone();
@@ -762,6 +1131,8 @@ impl FileHandler<(&'static str, Vec<LocaEntry>)> for Localization {
                 }
             }
         }
+
+        self.check_translations(&macros);
     }
 }
 
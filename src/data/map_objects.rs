@@ -0,0 +1,51 @@
+use std::path::PathBuf;
+
+use crate::block::Block;
+use crate::everything::Everything;
+use crate::fileset::{FileEntry, FileHandler, FileKind};
+use crate::item::Item;
+use crate::pdxfile::PdxFile;
+use crate::token::Token;
+
+/// The map object files place meshes and entities on the map.
+///
+/// These files are big and consist mostly of generated transforms, so only the mod's own files are
+/// loaded, and only their references to assets are kept.
+#[derive(Clone, Debug, Default)]
+pub struct MapObjects {
+    references: Vec<(Item, Token)>,
+}
+
+impl MapObjects {
+    pub fn validate(&self, data: &Everything) {
+        for (itype, token) in &self.references {
+            data.verify_exists(*itype, token);
+        }
+    }
+}
+
+impl FileHandler<Block> for MapObjects {
+    fn subpath(&self) -> PathBuf {
+        PathBuf::from(Item::MapObjectData.path())
+    }
+
+    fn load_file(&self, entry: &FileEntry) -> Option<Block> {
+        if entry.kind() != FileKind::Mod || !entry.filename().to_string_lossy().ends_with(".txt") {
+            return None;
+        }
+
+        PdxFile::read_optional_bom(entry)
+    }
+
+    fn handle_file(&mut self, _entry: &FileEntry, block: Block) {
+        for (key, block) in block.iter_definitions() {
+            if key.is("object") {
+                for (field, itype) in [("pdxmesh", Item::Pdxmesh), ("entity", Item::Entity)] {
+                    if let Some(token) = block.get_field_value(field) {
+                        self.references.push((itype, token.clone()));
+                    }
+                }
+            }
+        }
+    }
+}
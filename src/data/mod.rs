@@ -16,6 +16,7 @@ pub mod fonts;
 pub mod genes;
 pub mod gui;
 pub mod localization;
+pub mod map_objects;
 pub mod on_actions;
 pub mod portrait;
 pub mod script_values;
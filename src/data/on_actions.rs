@@ -42,6 +42,10 @@ impl OnActions {
         self.on_actions.values().map(|item| &item.key)
     }
 
+    pub fn iter_definitions(&self) -> impl Iterator<Item = (&Token, &Block)> {
+        self.on_actions.values().map(|item| (&item.key, &item.block))
+    }
+
     pub fn validate(&self, data: &Everything) {
         for item in self.on_actions.values() {
             item.validate(data);
@@ -250,6 +254,5 @@ pub fn validate_on_action(block: &Block, data: &Everything, sc: &mut ScopeContex
     vd.field_validated_block("effect", |b, data| {
         validate_effect(b, data, sc, Tooltipped::No);
     });
-    // TODO: check for infinite fallback loops?
     vd.field_item("fallback", Item::OnAction);
 }
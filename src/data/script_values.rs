@@ -49,6 +49,14 @@ impl ScriptValues {
         self.script_values.values().map(|item| &item.key)
     }
 
+    pub fn iter_definitions(&self) -> impl Iterator<Item = (&Token, &BV)> {
+        self.script_values.values().map(|item| (&item.key, &item.bv))
+    }
+
+    pub fn get_bv(&self, key: &str) -> Option<&BV> {
+        self.script_values.get(key).map(|item| &item.bv)
+    }
+
     pub fn validate(&self, data: &Everything) {
         for item in self.script_values.values() {
             item.validate(data);
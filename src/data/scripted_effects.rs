@@ -4,6 +4,7 @@ use std::path::PathBuf;
 use fnv::FnvHashMap;
 
 use crate::block::Block;
+use crate::callsites::CallSites;
 use crate::context::ScopeContext;
 use crate::effect::validate_effect;
 use crate::everything::Everything;
@@ -11,7 +12,7 @@ use crate::fileset::{FileEntry, FileHandler};
 use crate::helpers::{dup_error, exact_dup_error, BANNED_NAMES};
 use crate::macros::{MacroCache, MACRO_MAP};
 use crate::pdxfile::PdxFile;
-use crate::report::{err, old_warn, ErrorKey};
+use crate::report::{err, old_warn, warn_abbreviated, warn_header, will_log_abbreviated, ErrorKey};
 use crate::scopes::Scopes;
 use crate::token::Token;
 use crate::tooltipped::Tooltipped;
@@ -53,15 +54,54 @@ impl Effects {
         self.effects.values().map(|item| &item.key)
     }
 
+    pub fn iter_definitions(&self) -> impl Iterator<Item = (&Token, &Block)> {
+        self.effects.values().map(|item| (&item.key, &item.block))
+    }
+
     pub fn get(&self, key: &str) -> Option<&Effect> {
         self.effects.get(key)
     }
 
+    /// Stop recording the call sites of the scripted effects. This must be done before any of
+    /// them are validated on their own.
+    pub fn freeze_call_sites(&self) {
+        for item in self.effects.values() {
+            item.calls.freeze();
+        }
+    }
+
     pub fn validate(&self, data: &Everything) {
         for item in self.effects.values() {
             item.validate(data);
         }
     }
+
+    /// Compare the call sites of each scripted effect with each other. This must be done after all
+    /// validation is complete.
+    pub fn check_call_sites(&self) {
+        for item in self.effects.values() {
+            if item.scope_override.is_none() {
+                item.calls.check(&item.key, "scripted effect");
+            }
+        }
+    }
+
+    /// Report the scripted effects that are never called.
+    pub fn check_unused(&self) {
+        let mut vec: Vec<_> = self.effects.values().filter(|item| item.calls.is_unused()).collect();
+        vec.sort_unstable_by_key(|item| item.key.loc);
+        let mut printed_header = false;
+        for item in vec {
+            if !printed_header && will_log_abbreviated(&item.key, ErrorKey::UnusedScriptedItem) {
+                warn_header(ErrorKey::UnusedScriptedItem, "Unused scripted effects:\n");
+                printed_header = true;
+            }
+            warn_abbreviated(&item.key, ErrorKey::UnusedScriptedItem);
+        }
+        if printed_header {
+            warn_header(ErrorKey::UnusedScriptedItem, "\n");
+        }
+    }
 }
 
 impl FileHandler<Block> for Effects {
@@ -111,21 +151,49 @@ pub struct Effect {
     block: Block,
     cache: MacroCache<ScopeContext>,
     scope_override: Option<Scopes>,
+    calls: CallSites,
 }
 
 impl Effect {
     pub fn new(key: Token, block: Block, scope_override: Option<Scopes>) -> Self {
-        Self { key, block, cache: MacroCache::default(), scope_override }
+        Self {
+            key,
+            block,
+            cache: MacroCache::default(),
+            scope_override,
+            calls: CallSites::default(),
+        }
     }
 
+    /// Validate the scripted effect on its own, so that it is also checked if it's not called
+    /// from anywhere. This must be done after all other validation, so that the scope context can
+    /// be based on the call sites.
+    /// A scripted effect with macro parameters is validated once for each distinct set of
+    /// arguments it's called with.
     pub fn validate(&self, data: &Everything) {
         if self.block.source.is_none() {
+            let mut sc = self.standalone_sc();
+            validate_effect(&self.block, data, &mut sc, Tooltipped::No);
+        } else {
+            for (key, args) in self.calls.macro_calls() {
+                let args: Vec<_> =
+                    args.iter().map(|(parm, arg)| (parm.as_str(), arg.clone())).collect();
+                if let Some(block) = self.block.expand_macro(&args, key.loc) {
+                    let mut sc = self.standalone_sc();
+                    validate_effect(&block, data, &mut sc, Tooltipped::No);
+                }
+            }
+        }
+    }
+
+    fn standalone_sc(&self) -> ScopeContext {
+        if self.scope_override.is_some() {
             let mut sc = ScopeContext::new_unrooted(Scopes::all(), &self.key);
             sc.set_strict_scopes(false);
-            if self.scope_override.is_some() {
-                sc.set_no_warn(true);
-            }
-            self.validate_call(&self.key, data, &mut sc, Tooltipped::No);
+            sc.set_no_warn(true);
+            sc
+        } else {
+            self.calls.scope_context(&self.key)
         }
     }
 
@@ -136,6 +204,7 @@ impl Effect {
         sc: &mut ScopeContext,
         tooltipped: Tooltipped,
     ) {
+        self.calls.record(&self.key, key, &[], sc);
         if !self.cached_compat(key, &[], tooltipped, sc) {
             let mut our_sc = ScopeContext::new_unrooted(Scopes::all(), &self.key);
             our_sc.set_strict_scopes(false);
@@ -144,6 +213,7 @@ impl Effect {
             }
             self.cache.insert(key, &[], tooltipped, false, our_sc.clone());
             validate_effect(&self.block, data, &mut our_sc, tooltipped);
+            self.calls.record_body(&our_sc);
             if let Some(scopes) = self.scope_override {
                 our_sc = ScopeContext::new_unrooted(scopes, key);
                 our_sc.set_strict_scopes(false);
@@ -177,6 +247,7 @@ impl Effect {
         sc: &mut ScopeContext,
         tooltipped: Tooltipped,
     ) {
+        self.calls.record(&self.key, key, args, sc);
         // Every invocation is treated as different even if the args are the same,
         // because we want to point to the correct one when reporting errors.
         if !self.cached_compat(key, args, tooltipped, sc) {
@@ -190,6 +261,7 @@ impl Effect {
                 // that dummy context instead of macro-expanding again.
                 self.cache.insert(key, args, tooltipped, false, our_sc.clone());
                 validate_effect(&block, data, &mut our_sc, tooltipped);
+                self.calls.record_body(&our_sc);
                 if let Some(scopes) = self.scope_override {
                     our_sc = ScopeContext::new_unrooted(scopes, key);
                     our_sc.set_strict_scopes(false);
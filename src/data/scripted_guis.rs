@@ -6,7 +6,7 @@ use crate::effect::validate_effect;
 use crate::everything::Everything;
 use crate::game::GameFlags;
 use crate::item::{Item, ItemLoader};
-use crate::report::{old_warn, ErrorKey};
+use crate::report::{old_warn, warn, ErrorKey};
 use crate::scopes::Scopes;
 use crate::token::Token;
 use crate::tooltipped::Tooltipped;
@@ -25,6 +25,64 @@ impl ScriptedGui {
     pub fn add(db: &mut Db, key: Token, block: Block) {
         db.add(Item::ScriptedGui, key, block, Box::new(Self {}));
     }
+
+    /// Validate a call to the scripted gui `key` from a datatype expression.
+    /// `method` is the function called on it, such as `Execute` or `IsValid`.
+    /// `root` is the `SetRoot` in the call's `GuiScope` argument with the scope type it was given,
+    /// and `names` are the names given to `AddScope` there.
+    pub fn validate_call(
+        key: &Token,
+        block: &Block,
+        method: &Token,
+        root: Option<(&Token, Scopes)>,
+        names: &[&Token],
+    ) {
+        let field = match method.as_str() {
+            "Execute" | "ExecuteTooltip" => Some("effect"),
+            "IsValid" | "IsValidTooltip" => Some("is_valid"),
+            "IsShown" | "IsShownTooltip" => Some("is_shown"),
+            _ => None,
+        };
+        if let Some(field) = field {
+            if !block.has_key(field) {
+                let msg = format!("scripted gui `{key}` has no `{field}`");
+                if field == "effect" {
+                    let info = format!("`{method}` will do nothing");
+                    warn(ErrorKey::Datafunctions).msg(msg).info(info).loc(method).push();
+                } else {
+                    let info = format!("`{method}` will always be true");
+                    warn(ErrorKey::Datafunctions).weak().msg(msg).info(info).loc(method).push();
+                }
+            }
+        }
+
+        if let Some((setroot, scopes)) = root {
+            if let Some(token) = block.get_field_value("scope") {
+                if let Some(expect) = Scopes::from_snake_case(token.as_str()) {
+                    if !scopes.intersects(expect) {
+                        let msg =
+                            format!("`{setroot}` is given {scopes} but `{key}` expects {expect}");
+                        warn(ErrorKey::Scopes)
+                            .msg(msg)
+                            .loc(setroot)
+                            .loc(token, "scope defined here")
+                            .push();
+                    }
+                }
+            } else {
+                let msg = format!("scripted gui `{key}` has no `scope` for `{setroot}` to set");
+                warn(ErrorKey::Scopes).weak().msg(msg).loc(setroot).loc(key, "scripted gui").push();
+            }
+        }
+
+        let saved_scopes = block.get_field_list("saved_scopes").unwrap_or_default();
+        for name in names {
+            if !saved_scopes.iter().any(|saved| saved.is(name.as_str())) {
+                let msg = format!("`{name}` is not in the `saved_scopes` of scripted gui `{key}`");
+                warn(ErrorKey::Scopes).msg(msg).loc(*name).loc(key, "scripted gui").push();
+            }
+        }
+    }
 }
 
 impl DbKind for ScriptedGui {
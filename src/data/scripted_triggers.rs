@@ -3,6 +3,7 @@ use std::path::PathBuf;
 use fnv::FnvHashMap;
 
 use crate::block::Block;
+use crate::callsites::CallSites;
 use crate::context::ScopeContext;
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler, FileKind};
@@ -10,7 +11,9 @@ use crate::helpers::{dup_error, exact_dup_error, BANNED_NAMES};
 use crate::lowercase::Lowercase;
 use crate::macros::{MacroCache, MACRO_MAP};
 use crate::pdxfile::PdxFile;
-use crate::report::{err, old_warn, ErrorKey, Severity};
+use crate::report::{
+    err, old_warn, warn_abbreviated, warn_header, will_log_abbreviated, ErrorKey, Severity,
+};
 use crate::scopes::Scopes;
 use crate::token::Token;
 use crate::tooltipped::Tooltipped;
@@ -57,15 +60,55 @@ impl Triggers {
         self.triggers.values().map(|item| &item.key)
     }
 
+    pub fn iter_definitions(&self) -> impl Iterator<Item = (&Token, &Block)> {
+        self.triggers.values().map(|item| (&item.key, &item.block))
+    }
+
     pub fn get(&self, key: &str) -> Option<&Trigger> {
         self.triggers.get(key)
     }
 
+    /// Stop recording the call sites of the scripted triggers. This must be done before any of
+    /// them are validated on their own.
+    pub fn freeze_call_sites(&self) {
+        for item in self.triggers.values() {
+            item.calls.freeze();
+        }
+    }
+
     pub fn validate(&self, data: &Everything) {
         for item in self.triggers.values() {
             item.validate(data);
         }
     }
+
+    /// Compare the call sites of each scripted trigger with each other. This must be done after all
+    /// validation is complete.
+    pub fn check_call_sites(&self) {
+        for item in self.triggers.values() {
+            if item.scope_override.is_none() {
+                item.calls.check(&item.key, "scripted trigger");
+            }
+        }
+    }
+
+    /// Report the scripted triggers that are never called.
+    pub fn check_unused(&self) {
+        let mut vec: Vec<_> =
+            self.triggers.values().filter(|item| item.calls.is_unused()).collect();
+        vec.sort_unstable_by_key(|item| item.key.loc);
+        let mut printed_header = false;
+        for item in vec {
+            if !printed_header && will_log_abbreviated(&item.key, ErrorKey::UnusedScriptedItem) {
+                warn_header(ErrorKey::UnusedScriptedItem, "Unused scripted triggers:\n");
+                printed_header = true;
+            }
+            warn_abbreviated(&item.key, ErrorKey::UnusedScriptedItem);
+        }
+        if printed_header {
+            warn_header(ErrorKey::UnusedScriptedItem, "\n");
+        }
+    }
 }
 
 impl FileHandler<Block> for Triggers {
@@ -115,23 +158,67 @@ pub struct Trigger {
     block: Block,
     cache: MacroCache<ScopeContext>,
     scope_override: Option<Scopes>,
+    calls: CallSites,
 }
 
 impl Trigger {
     pub fn new(key: Token, block: Block, scope_override: Option<Scopes>) -> Self {
-        Self { key, block, cache: MacroCache::default(), scope_override }
+        Self {
+            key,
+            block,
+            cache: MacroCache::default(),
+            scope_override,
+            calls: CallSites::default(),
+        }
     }
 
+    /// Validate the scripted trigger on its own, so that it is also checked if it's not called
+    /// from anywhere. This must be done after all other validation, so that the scope context can
+    /// be based on the call sites.
+    /// A scripted trigger with macro parameters is validated once for each distinct set of
+    /// arguments it's called with.
     pub fn validate(&self, data: &Everything) {
-        // We could let triggers get "naturally" validated by being called from other places,
-        // but we want to also validate triggers that aren't called from anywhere yet.
         if self.block.source.is_none() {
+            let mut sc = self.standalone_sc();
+            validate_trigger_internal(
+                Lowercase::empty(),
+                false,
+                &self.block,
+                data,
+                &mut sc,
+                Tooltipped::No,
+                false,
+                Severity::Error,
+            );
+        } else {
+            for (key, args) in self.calls.macro_calls() {
+                let args: Vec<_> =
+                    args.iter().map(|(parm, arg)| (parm.as_str(), arg.clone())).collect();
+                if let Some(block) = self.block.expand_macro(&args, key.loc) {
+                    let mut sc = self.standalone_sc();
+                    validate_trigger_internal(
+                        Lowercase::empty(),
+                        false,
+                        &block,
+                        data,
+                        &mut sc,
+                        Tooltipped::No,
+                        false,
+                        Severity::Error,
+                    );
+                }
+            }
+        }
+    }
+
+    fn standalone_sc(&self) -> ScopeContext {
+        if self.scope_override.is_some() {
             let mut sc = ScopeContext::new_unrooted(Scopes::all(), &self.key);
             sc.set_strict_scopes(false);
-            if self.scope_override.is_some() {
-                sc.set_no_warn(true);
-            }
-            self.validate_call(&self.key, data, &mut sc, Tooltipped::No, false);
+            sc.set_no_warn(true);
+            sc
+        } else {
+            self.calls.scope_context(&self.key)
         }
     }
 
@@ -143,6 +230,7 @@ impl Trigger {
         tooltipped: Tooltipped,
         negated: bool,
     ) {
+        self.calls.record(&self.key, key, &[], sc);
         if !self.cached_compat(key, &[], tooltipped, negated, sc) {
             let mut our_sc = ScopeContext::new_unrooted(Scopes::all(), &self.key);
             our_sc.set_strict_scopes(false);
@@ -160,6 +248,7 @@ impl Trigger {
                 negated,
                 Severity::Error,
             );
+            self.calls.record_body(&our_sc);
             if let Some(scopes) = self.scope_override {
                 our_sc = ScopeContext::new_unrooted(scopes, key);
                 our_sc.set_strict_scopes(false);
@@ -195,6 +284,7 @@ impl Trigger {
         tooltipped: Tooltipped,
         negated: bool,
     ) {
+        self.calls.record(&self.key, key, args, sc);
         // Every invocation is treated as different even if the args are the same,
         // because we want to point to the correct one when reporting errors.
         if !self.cached_compat(key, args, tooltipped, negated, sc) {
@@ -217,6 +307,7 @@ impl Trigger {
                     negated,
                     Severity::Error,
                 );
+                self.calls.record_body(&our_sc);
                 if let Some(scopes) = self.scope_override {
                     our_sc = ScopeContext::new_unrooted(scopes, key);
                     our_sc.set_strict_scopes(false);
@@ -11,6 +11,7 @@ use strum_macros::{Display, EnumString};
 use crate::ck3::data::religions::CUSTOM_RELIGION_LOCAS;
 use crate::context::ScopeContext;
 use crate::data::customloca::CustomLocalization;
+use crate::data::scripted_guis::ScriptedGui;
 use crate::everything::Everything;
 use crate::game::Game;
 use crate::item::Item;
@@ -281,6 +282,43 @@ fn validate_custom(token: &Token, data: &Everything, scopes: Scopes, lang: &'sta
     }
 }
 
+/// Validate a call to a scripted gui, such as the `Execute` in
+/// `GetScriptedGui('name').Execute(GuiScope.SetRoot(...).AddScope('target', ...).End)`.
+///
+/// * `sgui`: The name of the scripted gui.
+/// * `code`: The code that follows the scripted gui in the chain.
+/// * `sc`: The available named scopes, for validating the `SetRoot` argument.
+/// * `lang`: The language being validated, can be "" when not applicable.
+pub fn validate_scripted_gui_call(
+    sgui: &Token,
+    code: &Code,
+    data: &Everything,
+    sc: &mut ScopeContext,
+    lang: &'static str,
+) {
+    let Some((key, block)) = data.get_key_block(Item::ScriptedGui, sgui.as_str()) else {
+        return;
+    };
+    let mut root = None;
+    let mut names = Vec::new();
+    if let Some(CodeArg::Chain(chain)) = code.arguments.first() {
+        if chain.codes.first().is_some_and(|scode| scode.name.is("GuiScope")) {
+            for scode in &chain.codes[1..] {
+                match (scode.name.as_str(), scode.arguments.first()) {
+                    ("SetRoot", Some(CodeArg::Chain(arg))) => {
+                        let dtype =
+                            validate_datatypes(arg, data, sc, Datatype::Unknown, lang, None, false);
+                        root = scope_from_datatype(dtype).map(|scopes| (&scode.name, scopes));
+                    }
+                    ("AddScope", Some(CodeArg::Literal(name))) => names.push(name),
+                    _ => (),
+                }
+            }
+        }
+    }
+    ScriptedGui::validate_call(key, block, &code.name, root, &names);
+}
+
 /// Internal function for validating an argument to a datatype code.
 /// If the argument is iself a code chain, this will end up calling `validate_datatypes` recursively.
 ///
@@ -352,6 +390,9 @@ fn validate_argument(
 /// * `expect_promote` is true iff the chain is expected to end on a promote rather than on a function.
 ///   Promotes and functions are very similar but they are defined separately in the datafunction tables
 ///   and usually only a function can end a chain.
+///
+/// Returns the datatype that the chain evaluates to, or `Datatype::Unknown` if it could not be
+/// determined or if there was an error.
 pub fn validate_datatypes(
     chain: &CodeChain,
     data: &Everything,
@@ -360,7 +401,7 @@ pub fn validate_datatypes(
     lang: &'static str,
     format: Option<&Token>,
     expect_promote: bool,
-) {
+) -> Datatype {
     let mut curtype = Datatype::Unknown;
     #[allow(unused_mut)] // vic3 does not need the mut
     let mut codes = Cow::from(&chain.codes[..]);
@@ -378,11 +419,11 @@ pub fn validate_datatypes(
                         let msg =
                             format!("substituted data bindings {macro_count} times, giving up");
                         err(ErrorKey::Macro).msg(msg).loc(&codes[i].name).push();
-                        return;
+                        return Datatype::Unknown;
                     }
                     codes.to_mut().splice(i..=i, replacement.codes);
                 } else {
-                    return;
+                    return Datatype::Unknown;
                 }
             }
         }
@@ -396,7 +437,7 @@ pub fn validate_datatypes(
         if code.name.is("") {
             // TODO: verify if the game engine is okay with this
             warn(ErrorKey::Datafunctions).msg("empty fragment").loc(&code.name).push();
-            return;
+            return Datatype::Unknown;
         }
 
         let lookup_gf = lookup_global_function(code.name.as_str());
@@ -433,7 +474,7 @@ pub fn validate_datatypes(
                 LookupResult::WrongType => {
                     let msg = format!("{} can not follow a {curtype} promote", code.name);
                     warn(ErrorKey::Datafunctions).msg(msg).loc(&code.name).push();
-                    return;
+                    return Datatype::Unknown;
                 }
                 LookupResult::NotFound => (),
             }
@@ -447,7 +488,7 @@ pub fn validate_datatypes(
                 LookupResult::WrongType => {
                     let msg = format!("{} can not follow a {curtype} promote", code.name);
                     warn(ErrorKey::Datafunctions).msg(msg).loc(&code.name).push();
-                    return;
+                    return Datatype::Unknown;
                 }
                 LookupResult::NotFound => (),
             }
@@ -461,33 +502,33 @@ pub fn validate_datatypes(
             if is_first && (p_found || f_found) && !gp_found && !gf_found {
                 let msg = format!("{} can not be the first in a chain", code.name);
                 warn(ErrorKey::Datafunctions).msg(msg).loc(&code.name).push();
-                return;
+                return Datatype::Unknown;
             }
             if is_last && (gp_found || p_found) && !gf_found && !f_found && !expect_promote {
                 let msg = format!("{} can not be last in a chain", code.name);
                 warn(ErrorKey::Datafunctions).msg(msg).loc(&code.name).push();
-                return;
+                return Datatype::Unknown;
             }
             if expect_promote && (gf_found || f_found) {
                 let msg = format!("{} can not be used in this field", code.name);
                 warn(ErrorKey::Datafunctions).msg(msg).loc(&code.name).push();
-                return;
+                return Datatype::Unknown;
             }
             if !is_first && (gp_found || gf_found) && !p_found && !f_found {
                 let msg = format!("{} must be the first in a chain", code.name);
                 warn(ErrorKey::Datafunctions).msg(msg).loc(&code.name).push();
-                return;
+                return Datatype::Unknown;
             }
             if !is_last && (gf_found || f_found) && !gp_found && !p_found {
                 let msg = format!("{} must be last in the chain", code.name);
                 warn(ErrorKey::Datafunctions).msg(msg).loc(&code.name).push();
-                return;
+                return Datatype::Unknown;
             }
             // A catch-all condition if none of the above match
             if gp_found || gf_found || p_found || f_found {
                 let msg = format!("{} is improperly used here", code.name);
                 warn(ErrorKey::Datafunctions).msg(msg).loc(&code.name).push();
-                return;
+                return Datatype::Unknown;
             }
         }
 
@@ -608,7 +649,7 @@ pub fn validate_datatypes(
             } else {
                 warn(ErrorKey::Datafunctions).msg(msg).loc(&code.name).push();
             }
-            return;
+            return Datatype::Unknown;
         }
 
         // This `if let` skips this check if args is `Args::Unknown`
@@ -621,7 +662,7 @@ pub fn validate_datatypes(
                     code.arguments.len()
                 );
                 warn(ErrorKey::Datafunctions).msg(msg).loc(&code.name).push();
-                return;
+                return Datatype::Unknown;
             }
         }
 
@@ -684,6 +725,12 @@ pub fn validate_datatypes(
             }
         }
 
+        if i > 0 && codes[i - 1].name.is("GetScriptedGui") {
+            if let Some(CodeArg::Literal(sgui)) = codes[i - 1].arguments.first() {
+                validate_scripted_gui_call(sgui, code, data, sc, lang);
+            }
+        }
+
         if let Args::Args(a) = args {
             for (i, arg) in a.iter().enumerate() {
                 // Handle |E that contain a SelectLocalization that chooses between two gameconcepts
@@ -713,18 +760,19 @@ pub fn validate_datatypes(
                     let msg =
                         format!("{} returns {curtype} but a scope type is needed here", code.name);
                     warn(ErrorKey::Datafunctions).msg(msg).loc(&code.name).push();
-                    return;
+                    return Datatype::Unknown;
                 }
             } else {
                 let msg =
                     format!("{} returns {curtype} but a {expect_type} is needed here", code.name);
                 warn(ErrorKey::Datafunctions).msg(msg).loc(&code.name).push();
-                return;
+                return Datatype::Unknown;
             }
         }
 
         i += 1;
     }
+    curtype
 }
 
 fn lookup_global_promote(lookup_name: &str) -> Option<(Args, Datatype)> {
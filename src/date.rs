@@ -16,6 +16,24 @@ impl Date {
     pub fn new(year: i16, month: i8, day: i8) -> Self {
         Date { year, month, day }
     }
+
+    /// Return the date `years` years later, or earlier if `years` is negative.
+    #[cfg(feature = "ck3")] // vic3 happens not to use
+    #[must_use]
+    pub fn add_years(self, years: i16) -> Self {
+        Date { year: self.year.saturating_add(years), ..self }
+    }
+
+    /// Return the date `months` months later, or earlier if `months` is negative.
+    /// The day of the month is kept as is.
+    #[cfg(feature = "ck3")] // vic3 happens not to use
+    #[must_use]
+    pub fn add_months(self, months: i16) -> Self {
+        let total = i32::from(self.year) * 12 + i32::from(self.month) - 1 + i32::from(months);
+        // The casts can't overflow for realistic dates
+        #[allow(clippy::cast_possible_truncation)]
+        Date { year: total.div_euclid(12) as i16, month: total.rem_euclid(12) as i8 + 1, ..self }
+    }
 }
 
 impl FromStr for Date {
@@ -1,7 +1,6 @@
 //! Validator for the `.dds` (picture) files that are used in the game.
 
-use std::fs::{metadata, File};
-use std::io::{Read, Result};
+use std::io::{Error, ErrorKind, Result};
 use std::path::PathBuf;
 
 use fnv::FnvHashMap;
@@ -10,6 +9,7 @@ use crate::fileset::{FileEntry, FileHandler};
 use crate::report::{advice_info, error, error_info, old_warn, ErrorKey};
 #[cfg(feature = "ck3")]
 use crate::token::Token;
+use crate::vfs::read_prefix;
 
 const DDS_HEADER_SIZE: usize = 124;
 const DDS_HEIGHT_OFFSET: usize = 12;
@@ -29,13 +29,14 @@ pub struct DdsFiles {
 
 impl DdsFiles {
     fn load_dds(entry: &FileEntry) -> Result<Option<DdsInfo>> {
-        if metadata(entry.fullpath())?.len() == 0 {
+        let buffer = read_prefix(entry.fullpath(), DDS_HEADER_SIZE)?;
+        if buffer.is_empty() {
             old_warn(entry, ErrorKey::ImageFormat, "empty file");
             return Ok(None);
         }
-        let mut f = File::open(entry.fullpath())?;
-        let mut buffer = [0; DDS_HEADER_SIZE];
-        f.read_exact(&mut buffer)?;
+        if buffer.len() < DDS_HEADER_SIZE {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "failed to fill whole buffer"));
+        }
         if buffer.starts_with(b"\x89PNG") {
             let msg = "actually a PNG";
             let info =
@@ -54,6 +55,11 @@ impl DdsFiles {
         self.dds_files.insert(entry.path().to_string_lossy().to_string(), info);
     }
 
+    /// Return the width and height of the dds file at `path`, if it was loaded.
+    pub fn get_size(&self, path: &str) -> Option<(u32, u32)> {
+        self.dds_files.get(path).map(|info| (info.width, info.height))
+    }
+
     #[cfg(feature = "ck3")]
     pub fn validate_frame(&self, key: &Token, width: u32, height: u32, frame: u32) {
         // Note: `frame` is 1-based
@@ -103,9 +109,7 @@ impl FileHandler<DdsInfo> for DdsFiles {
 
 #[derive(Copy, Clone, Debug)]
 pub struct DdsInfo {
-    #[allow(dead_code)] // vic3 doesn't use
     width: u32,
-    #[allow(dead_code)] // vic3 doesn't use
     height: u32,
 }
 
@@ -10,7 +10,7 @@ use crate::item::Item;
 use crate::lowercase::Lowercase;
 use crate::report::{advice_info, err, error, fatal, old_warn, warn_info, ErrorKey};
 use crate::scopes::{scope_iterator, Scopes};
-use crate::script_value::validate_script_value;
+use crate::script_value::{check_chance, validate_script_value};
 use crate::token::Token;
 use crate::tooltipped::Tooltipped;
 #[cfg(feature = "ck3")]
@@ -96,7 +96,7 @@ pub fn validate_effect_internal<'a>(
         validate_inside_iterator(caller, list_type, block, data, sc, &mut vd, tooltipped);
     }
 
-    validate_ifelse_sequence(block, "if", "else_if", "else");
+    validate_ifelse_sequence(block, data, "if", "else_if", "else");
 
     vd.set_allow_questionmark_equals(true);
     vd.unknown_fields_cmp(|key, cmp, bv| {
@@ -445,6 +445,9 @@ pub fn validate_effect_control(
     if caller == "random" {
         vd.req_field("chance");
         vd.field_script_value("chance", sc);
+        if let (Some(key), Some(bv)) = (block.get_key("chance"), block.get_field("chance")) {
+            check_chance(key, bv, data);
+        }
     } else {
         vd.ban_field("chance", || "`random`");
     }
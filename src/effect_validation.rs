@@ -10,7 +10,7 @@ use crate::item::Item;
 use crate::lowercase::Lowercase;
 use crate::report::{err, warn, ErrorKey, Severity};
 use crate::scopes::Scopes;
-use crate::script_value::validate_script_value;
+use crate::script_value::{eval_script_value, validate_script_value};
 use crate::token::Token;
 use crate::tooltipped::Tooltipped;
 use crate::trigger::{validate_target_ok_this, validate_trigger_key_bv};
@@ -77,6 +77,10 @@ pub fn validate_clamp_variable(
     vd.field_script_value("max", sc);
 }
 
+/// The fields that can change the weight of a `random_list` entry.
+const WEIGHT_MODIFIERS: &[&str] =
+    &["modifier", "compare_modifier", "opinion_modifier", "ai_value_modifier", "compatibility"];
+
 /// A specific validator for the `random_list` effect, which has a unique syntax.
 pub fn validate_random_list(
     key: &Token,
@@ -101,12 +105,44 @@ pub fn validate_random_list(
             } else if n.fract() != 0.0 {
                 let msg = "fractions are discarded in `random_list` weights";
                 warn(ErrorKey::Range).strong().msg(msg).loc(key).push();
+            } else if n == 0.0 && !WEIGHT_MODIFIERS.iter().any(|name| block.has_key(name)) {
+                let msg = "this `random_list` entry has a weight of 0 and no modifiers";
+                let info = "it will never be chosen";
+                warn(ErrorKey::Range).msg(msg).info(info).loc(key).push();
+            } else if n == 0.0 && weight_never_raised(block, data) {
+                let msg =
+                    "this `random_list` entry has a weight of 0 and its modifiers never raise it";
+                let info = "it will never be chosen";
+                warn(ErrorKey::Range).msg(msg).info(info).loc(key).push();
             }
             validate_effect_control(&caller, block, data, sc, tooltipped);
         }
     });
 }
 
+/// Return true iff the weight modifiers of a `random_list` entry can be determined in advance to
+/// never raise its weight above 0. Only `modifier` blocks can be evaluated this way.
+fn weight_never_raised(block: &Block, data: &Everything) -> bool {
+    block
+        .iter_fields()
+        .filter(|field| WEIGHT_MODIFIERS.iter().any(|name| field.key().is(name)))
+        .all(|field| {
+            let Some(block) = field.bv().get_block().filter(|_| field.key().is("modifier")) else {
+                return false;
+            };
+            block.iter_fields().all(|field| {
+                if field.key().is("add") {
+                    eval_script_value(field.bv(), data).is_some_and(|range| range.max <= 0.0)
+                } else if field.key().is("factor") {
+                    // A negative factor could turn a negative weight positive
+                    eval_script_value(field.bv(), data).is_some_and(|range| range.min >= 0.0)
+                } else {
+                    true
+                }
+            })
+        })
+}
+
 pub fn validate_remove_from_list(
     _key: &Token,
     mut vd: ValueValidator,
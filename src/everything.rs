@@ -4,10 +4,13 @@
 //! that individual functions can access all the defined game items.
 
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 #[cfg(feature = "ck3")]
 use std::sync::RwLock;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
 #[cfg(feature = "ck3")]
@@ -19,6 +22,7 @@ use thiserror::Error;
 use crate::block::Block;
 #[cfg(feature = "ck3")]
 use crate::block::BV;
+use crate::call_graph::check_call_graph;
 #[cfg(feature = "ck3")]
 use crate::ck3::data::{
     characters::Characters, data_binding::DataBindings, doctrines::Doctrines, events::Ck3Events,
@@ -28,7 +32,7 @@ use crate::ck3::data::{
 };
 #[cfg(feature = "ck3")]
 use crate::ck3::tables::misc::*;
-use crate::config_load::{check_for_legacy_ignore, load_filter};
+use crate::config_load::{check_for_legacy_ignore, load_filter, load_overrides};
 use crate::context::ScopeContext;
 use crate::data::{
     assets::Assets,
@@ -36,6 +40,7 @@ use crate::data::{
     defines::Defines,
     gui::Gui,
     localization::Localization,
+    map_objects::MapObjects,
     on_actions::OnActions,
     script_values::ScriptValues,
     scripted_effects::{Effect, Effects},
@@ -43,9 +48,12 @@ use crate::data::{
     scripted_modifiers::ScriptedModifiers,
     scripted_triggers::{Trigger, Triggers},
 };
+#[cfg(feature = "ck3")]
+use crate::date::Date;
 use crate::db::{Db, DbKind};
 use crate::dds::DdsFiles;
 use crate::fileset::{FileEntry, FileKind, Fileset};
+use crate::fontfiles::FontFiles;
 use crate::game::Game;
 #[cfg(feature = "imperator")]
 use crate::imperator::tables::misc::*;
@@ -57,9 +65,14 @@ use crate::parse::json::parse_json_file;
 use crate::pdxfile::PdxFile;
 #[cfg(feature = "ck3")]
 use crate::report::err;
-use crate::report::{report, set_output_style, ErrorKey, OutputStyle, Severity};
+use crate::report::{
+    emitted_report_counts, report, set_output_style, warn, ErrorKey, OutputStyle, Severity,
+};
 use crate::rivers::Rivers;
+use crate::shaders::Shaders;
+use crate::summary::{Summary, TOP_FILES};
 use crate::token::{Loc, Token};
+use crate::vfs::is_file;
 #[cfg(feature = "vic3")]
 use crate::vic3::data::{
     buy_packages::BuyPackage, events::Vic3Events, history::History, provinces::Vic3Provinces,
@@ -93,6 +106,9 @@ pub struct Everything {
     /// Config from file
     config: Block,
 
+    /// The wall-clock time taken by each phase of the run so far, for the run summary.
+    phases: Mutex<Vec<(&'static str, Duration)>>,
+
     /// A cache of define values (from common/defines) that are missing and that have already been
     /// warned about as missing. This is to avoid duplicate warnings.
     #[cfg(feature = "ck3")] // happens not to be used by vic3
@@ -104,6 +120,12 @@ pub struct Everything {
     /// Tracks specifically the .dds files, and their formats and sizes.
     pub(crate) dds: DdsFiles,
 
+    /// Tracks the font files and which characters they can display.
+    pub(crate) fonts: FontFiles,
+
+    /// Tracks the effects defined in the shader files.
+    pub(crate) shaders: Shaders,
+
     /// A general database of item types. Most items go here. The ones that need special handling
     /// go in the separate databases listed below.
     pub(crate) database: Db,
@@ -164,6 +186,7 @@ pub struct Everything {
     pub(crate) data_bindings: DataBindings,
 
     pub(crate) assets: Assets,
+    pub(crate) map_objects: MapObjects,
     #[cfg(feature = "ck3")]
     pub(crate) music: Musics,
 
@@ -203,7 +226,7 @@ impl Everything {
         };
 
         let config_file = mod_root.join(config_file_name);
-        let config = if config_file.is_file() {
+        let config = if is_file(&config_file) {
             Self::_read_config(config_file_name, &config_file)
                 .ok_or(FilesError::ConfigUnreadable { path: config_file })?
         } else {
@@ -212,13 +235,18 @@ impl Everything {
 
         fileset.config(config.clone());
 
+        let start = Instant::now();
         fileset.scan_all()?;
         fileset.finalize();
+        let phases = Mutex::new(vec![("scan", start.elapsed())]);
 
         Ok(Everything {
             fileset,
             dds: DdsFiles::default(),
+            fonts: FontFiles::default(),
+            shaders: Shaders::default(),
             config,
+            phases,
             #[cfg(feature = "ck3")]
             warned_defines: RwLock::new(FnvHashSet::default()),
             database: Db::default(),
@@ -260,6 +288,7 @@ impl Everything {
             #[cfg(feature = "ck3")]
             data_bindings: DataBindings::default(),
             assets: Assets::default(),
+            map_objects: MapObjects::default(),
             #[cfg(feature = "ck3")]
             music: Musics::default(),
             coas: Coas::default(),
@@ -278,6 +307,7 @@ impl Everything {
     pub fn load_config_filtering_rules(&self) {
         check_for_legacy_ignore(&self.config);
         load_filter(&self.config);
+        load_overrides(&self.config);
     }
 
     /// Load the `OutputStyle` settings from the config.
@@ -359,6 +389,8 @@ impl Everything {
     fn load_all_generic(&mut self) {
         scope(|s| {
             s.spawn(|_| self.fileset.handle(&mut self.dds));
+            s.spawn(|_| self.fileset.handle(&mut self.fonts));
+            s.spawn(|_| self.fileset.handle(&mut self.shaders));
             s.spawn(|_| self.fileset.handle(&mut self.localization));
             s.spawn(|_| self.fileset.handle(&mut self.scripted_lists));
             s.spawn(|_| self.fileset.handle(&mut self.defines));
@@ -367,6 +399,7 @@ impl Everything {
             s.spawn(|_| self.fileset.handle(&mut self.triggers));
             s.spawn(|_| self.fileset.handle(&mut self.effects));
             s.spawn(|_| self.fileset.handle(&mut self.assets));
+            s.spawn(|_| self.fileset.handle(&mut self.map_objects));
             s.spawn(|_| self.fileset.handle(&mut self.gui));
             s.spawn(|_| self.fileset.handle(&mut self.on_actions));
             s.spawn(|_| self.fileset.handle(&mut self.coas));
@@ -408,6 +441,7 @@ impl Everything {
     fn load_all_imperator(&mut self) {}
 
     pub fn load_all(&mut self) {
+        let start = Instant::now();
         self.load_all_generic();
         match Game::game() {
             #[cfg(feature = "ck3")]
@@ -417,6 +451,7 @@ impl Everything {
             #[cfg(feature = "imperator")]
             Game::Imperator => self.load_all_imperator(),
         }
+        self.record_phase("load", start);
     }
 
     fn validate_all_generic<'a>(&'a self, s: &Scope<'a>) {
@@ -425,10 +460,10 @@ impl Everything {
         s.spawn(|_| self.defines.validate(self));
         s.spawn(|_| self.scripted_modifiers.validate(self));
         s.spawn(|_| self.script_values.validate(self));
-        s.spawn(|_| self.triggers.validate(self));
-        s.spawn(|_| self.effects.validate(self));
         s.spawn(|_| self.assets.validate(self));
+        s.spawn(|_| self.map_objects.validate(self));
         s.spawn(|_| self.gui.validate(self));
+        s.spawn(|_| self.shaders.validate(self));
         s.spawn(|_| self.on_actions.validate(self));
         s.spawn(|_| self.coas.validate(self));
     }
@@ -463,6 +498,7 @@ impl Everything {
     // Imperator one goes here when needed
 
     pub fn validate_all(&self) {
+        let start = Instant::now();
         scope(|s| {
             self.validate_all_generic(s);
             match Game::game() {
@@ -475,17 +511,32 @@ impl Everything {
             }
         });
         self.database.validate(self);
+        // Scripted triggers and effects are validated on their own once all their call sites are known.
+        // Their calls to each other from then on are not recorded, so the order doesn't matter.
+        self.triggers.freeze_call_sites();
+        self.effects.freeze_call_sites();
+        scope(|s| {
+            s.spawn(|_| self.triggers.validate(self));
+            s.spawn(|_| self.effects.validate(self));
+        });
+        self.triggers.check_call_sites();
+        self.effects.check_call_sites();
+        check_call_graph(self);
 
         self.localization.validate_pass2(self);
+        self.localization.check_glyphs(self);
 
         // Clear macro map
         MACRO_MAP.clear();
+        self.record_phase("validate", start);
     }
 
     pub fn check_rivers(&mut self) {
+        let start = Instant::now();
         let mut rivers = Rivers::default();
         self.fileset.handle(&mut rivers);
         rivers.validate(self);
+        self.record_phase("rivers", start);
     }
 
     #[cfg(feature = "ck3")]
@@ -496,8 +547,85 @@ impl Everything {
     }
 
     pub fn check_unused(&mut self) {
+        let start = Instant::now();
         self.localization.check_unused(self);
         self.fileset.check_unused_dds(self);
+        self.triggers.check_unused();
+        self.effects.check_unused();
+        self.gui.check_unused();
+        self.assets.check_unused();
+        self.record_phase("unused", start);
+    }
+
+    fn record_phase(&self, phase: &'static str, start: Instant) {
+        self.phases.lock().unwrap().push((phase, start.elapsed()));
+    }
+
+    /// Collect the summary statistics of this run.
+    /// The report counts only include the reports that have already been emitted, which leaves
+    /// out the abbreviated reports of [`Everything::check_unused`].
+    ///
+    /// # Panics
+    /// May panic when the mutex has been poisoned by another thread.
+    pub fn summary(&self) -> Summary {
+        let reports = emitted_report_counts();
+        let mut items = BTreeMap::new();
+        for itype in Item::iter() {
+            let count =
+                self.iter_keys(itype).filter(|token| token.loc.kind == FileKind::Mod).count();
+            if count > 0 {
+                let name: &str = itype.into();
+                items.insert(name.to_string(), count);
+            }
+        }
+        let phases = self.phases.lock().unwrap();
+        Summary {
+            top_files: reports.top_files(TOP_FILES),
+            reports,
+            mod_files: self.fileset.count_mod_files(),
+            items,
+            phases: phases
+                .iter()
+                .map(|(phase, time)| ((*phase).to_string(), time.as_secs_f64()))
+                .collect(),
+        }
+    }
+
+    /// Return the holder of `title` on `date`, according to the title history.
+    #[cfg(feature = "ck3")]
+    pub fn title_holder_at(&self, title: &str, date: Date) -> Option<&Token> {
+        self.title_history.holder_at(title, date)
+    }
+
+    /// Return the liege title of `title` on `date`, according to the title history.
+    #[cfg(feature = "ck3")]
+    pub fn title_liege_at(&self, title: &str, date: Date) -> Option<&Token> {
+        self.title_history.liege_at(title, date)
+    }
+
+    /// Return the de jure liege title of `title` on `date`, according to the title history and
+    /// the landed titles.
+    #[cfg(feature = "ck3")]
+    pub fn title_de_jure_liege_at(&self, title: &str, date: Date) -> Option<String> {
+        self.title_history.de_jure_liege_at(title, date, self)
+    }
+
+    /// Return a summary of how much of the mod's english localization is translated to each
+    /// checked language.
+    pub fn loca_coverage_report(&self) -> String {
+        self.localization.coverage_report()
+    }
+
+    /// Describe the fully resolved widget tree of the gui window, widget or type `name`,
+    /// with the file and line that each part came from.
+    pub fn gui_tree(&self, name: &str) -> Option<String> {
+        self.gui.widget_tree(name)
+    }
+
+    /// Write stub localization files, with the english text, for keys that the mod's
+    /// translations are missing. Returns the paths of the files written.
+    pub fn write_loca_stubs(&self, dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+        self.localization.write_stubs(dir)
     }
 
     pub(crate) fn item_has_property(&self, itype: Item, key: &str, property: &str) -> bool {
@@ -625,6 +753,7 @@ impl Everything {
             Item::ScriptedModifier => self.scripted_modifiers.exists(key),
             Item::ScriptedTrigger => self.triggers.exists(key),
             Item::ScriptValue => self.script_values.exists(key),
+            Item::ShaderEffect => self.shaders.effect_exists(key),
             Item::TextFormat => self.gui.textformat_exists(key),
             Item::TextIcon => self.gui.texticon_exists(key),
             Item::TextureFile => self.assets.texture_exists(key),
@@ -644,6 +773,8 @@ impl Everything {
         match itype {
             Item::File => self.fileset.mark_used(key),
             Item::Localization => self.localization.mark_used(key),
+            Item::GuiLayer | Item::TextFormat | Item::TextIcon => self.gui.mark_used(itype, key),
+            Item::Asset | Item::Entity | Item::Pdxmesh => self.assets.mark_used(itype, key),
             _ => (),
         }
     }
@@ -676,10 +807,23 @@ impl Everything {
                 #[cfg(feature = "vic3")]
                 Game::Vic3 => self.provinces_vic3.verify_exists_implied(key, token, max_sev),
             },
+            Item::Sound if key.starts_with("file:/") => {
+                // The remaining leading slash, if any, is stripped by the fileset.
+                let file = key.strip_prefix("file:/").unwrap();
+                self.fileset.verify_exists_implied(file, token, itype.severity().at_most(max_sev));
+            }
             Item::TextureFile => {
                 if let Some(entry) = self.assets.get_texture(key) {
                     // TODO: avoid allocating a string here
                     self.fileset.mark_used(&entry.path().to_string_lossy());
+                } else if let Some(entry) = self.assets.get_texture_case_insensitive(key) {
+                    self.fileset.mark_used(&entry.path().to_string_lossy());
+                    let msg = format!(
+                        "texture file {key} only exists as {}",
+                        entry.filename().to_string_lossy()
+                    );
+                    let info = "this works on Windows, but not on Linux or Mac where file names are case-sensitive";
+                    warn(ErrorKey::Portability).msg(msg).info(info).loc(token).push();
                 } else {
                     let msg = format!("no texture file {key} anywhere under {}", itype.path());
                     report(ErrorKey::MissingFile, itype.severity().at_most(max_sev))
@@ -690,6 +834,7 @@ impl Everything {
                 }
             }
             _ => {
+                self.mark_used(itype, key);
                 if !self.item_exists(itype, key) {
                     let path = itype.path();
                     let msg = if path.is_empty() {
@@ -745,6 +890,19 @@ impl Everything {
         self.database.get_key_block(itype, key)
     }
 
+    /// Iterate over the definitions of all events, in the games where events are loaded.
+    #[allow(clippy::unused_self)] // TODO - imperator - does not use
+    pub(crate) fn iter_event_definitions(&self) -> Box<dyn Iterator<Item = (&Token, &Block)> + '_> {
+        match Game::game() {
+            #[cfg(feature = "ck3")]
+            Game::Ck3 => Box::new(self.events_ck3.iter_definitions()),
+            #[cfg(feature = "vic3")]
+            Game::Vic3 => Box::new(self.events_vic3.iter_definitions()),
+            #[cfg(feature = "imperator")]
+            Game::Imperator => Box::new(std::iter::empty()),
+        }
+    }
+
     pub(crate) fn get_trigger(&self, key: &Token) -> Option<&Trigger> {
         #[cfg(feature = "ck3")]
         if Game::is_ck3() {
@@ -881,6 +1039,7 @@ impl Everything {
             Item::ScriptedModifier => Box::new(self.scripted_modifiers.iter_keys()),
             Item::ScriptedTrigger => Box::new(self.triggers.iter_keys()),
             Item::ScriptValue => Box::new(self.script_values.iter_keys()),
+            Item::ShaderEffect => Box::new(self.shaders.iter_effect_keys()),
             Item::TextFormat => Box::new(self.gui.iter_textformat_keys()),
             Item::TextIcon => Box::new(self.gui.iter_texticon_keys()),
             Item::TextureFile => Box::new(self.assets.iter_texture_keys()),
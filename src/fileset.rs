@@ -8,7 +8,7 @@ use std::string::ToString;
 use std::sync::RwLock;
 
 use anyhow::Result;
-use fnv::FnvHashSet;
+use fnv::{FnvHashMap, FnvHashSet};
 use rayon::prelude::*;
 use walkdir::WalkDir;
 
@@ -20,10 +20,14 @@ use crate::item::Item;
 use crate::modfile::ModFile;
 use crate::pathtable::{PathTable, PathTableIndex};
 use crate::report::{
-    add_loaded_mod_root, err, error, fatal, report, warn_abbreviated, warn_header, will_maybe_log,
-    ErrorKey, Severity,
+    add_loaded_mod_root, err, error, fatal, report, warn, warn_abbreviated, warn_header,
+    will_log_abbreviated, ErrorKey, Severity,
 };
 use crate::token::Token;
+use crate::vfs::{archive_files_under, open_mod_archive};
+
+/// The longest full pathname that Windows will open without special measures.
+const WINDOWS_MAX_PATH: usize = 260;
 
 /// Note that ordering of these enum values matters.
 /// Files later in the order will override files of the same name before them,
@@ -220,6 +224,10 @@ pub struct Fileset {
     /// All filenames from ordered_files, for quick lookup
     filenames: FnvHashSet<PathBuf>,
 
+    /// All filenames from `ordered_files`, keyed by their lowercased form.
+    /// Used to find references that only work on case-insensitive filesystems such as Windows.
+    filenames_lowercase: FnvHashMap<String, Vec<PathBuf>>,
+
     /// All directories that have been looked up, for quick lookup
     directories: RwLock<FnvHashSet<PathBuf>>,
 
@@ -244,6 +252,7 @@ impl Fileset {
             ordered_files: Vec::new(),
             filename_tokens: Vec::new(),
             filenames: FnvHashSet::default(),
+            filenames_lowercase: FnvHashMap::default(),
             directories: RwLock::new(FnvHashSet::default()),
             used: RwLock::new(FnvHashSet::default()),
         }
@@ -265,7 +274,18 @@ impl Fileset {
                 block.get_field_value("label").map_or_else(default_label, ToString::to_string);
             if Game::is_ck3() || Game::is_imperator() {
                 if let Some(path) = block.get_field_value("modfile") {
-                    let path = PathBuf::from(path.as_str());
+                    let mut path = PathBuf::from(path.as_str());
+                    if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("zip")) {
+                        match open_mod_archive(&path) {
+                            Ok(root) => path = root.join("descriptor.mod"),
+                            Err(e) => {
+                                let msg = format!("could not open mod archive {}", path.display());
+                                let info = format!("{e:#}");
+                                err(ErrorKey::Config).msg(msg).info(info).loc(block).push();
+                                continue;
+                            }
+                        }
+                    }
                     if let Ok(modfile) = ModFile::read(&path) {
                         let mod_name_append = if let Some(name) = modfile.display_name() {
                             format!(" \"{name}\"")
@@ -331,6 +351,14 @@ impl Fileset {
     }
 
     fn scan(&mut self, path: &Path, kind: FileKind) -> Result<(), walkdir::Error> {
+        if let Some(files) = archive_files_under(path) {
+            for fullpath in files {
+                // unwrap is safe here because `archive_files_under` gives us paths with this prefix.
+                let inner_path = fullpath.strip_prefix(path).unwrap();
+                self.add_scanned(inner_path, kind, &fullpath);
+            }
+            return Ok(());
+        }
         for entry in WalkDir::new(path) {
             let entry = entry?;
             if entry.depth() == 0 || !entry.file_type().is_file() {
@@ -338,22 +366,22 @@ impl Fileset {
             }
             // unwrap is safe here because WalkDir gives us paths with this prefix.
             let inner_path = entry.path().strip_prefix(path).unwrap();
-            if inner_path.starts_with(".git") {
-                continue;
-            }
-            let inner_dir = inner_path.parent().unwrap_or_else(|| Path::new(""));
-            if self.should_replace(inner_dir, kind) {
-                continue;
-            }
-            self.files.push(FileEntry::new(
-                inner_path.to_path_buf(),
-                kind,
-                entry.path().to_path_buf(),
-            ));
+            self.add_scanned(inner_path, kind, entry.path());
         }
         Ok(())
     }
 
+    fn add_scanned(&mut self, inner_path: &Path, kind: FileKind, fullpath: &Path) {
+        if inner_path.starts_with(".git") {
+            return;
+        }
+        let inner_dir = inner_path.parent().unwrap_or_else(|| Path::new(""));
+        if self.should_replace(inner_dir, kind) {
+            return;
+        }
+        self.files.push(FileEntry::new(inner_path.to_path_buf(), kind, fullpath.to_path_buf()));
+    }
+
     pub fn scan_all(&mut self) -> Result<(), FilesError> {
         if let Some(clausewitz_root) = self.clausewitz_root.clone() {
             self.scan(&clausewitz_root.clone(), FileKind::Clausewitz).map_err(|e| {
@@ -406,9 +434,18 @@ impl Fileset {
             self.filename_tokens.push(token);
             entry.store_in_pathtable();
             self.filenames.insert(entry.path.clone());
+            self.filenames_lowercase
+                .entry(entry.path.to_string_lossy().to_lowercase())
+                .or_default()
+                .push(entry.path.clone());
         }
     }
 
+    /// Return how many of the files are from the mod itself.
+    pub fn count_mod_files(&self) -> usize {
+        self.ordered_files.iter().filter(|entry| entry.kind == FileKind::Mod).count()
+    }
+
     pub fn get_files_under<'a>(&'a self, subpath: &'a Path) -> &[FileEntry] {
         let start = self.ordered_files.partition_point(|entry| entry.path < subpath);
         let end = start
@@ -453,6 +490,39 @@ impl Fileset {
         self.filename_tokens.iter()
     }
 
+    /// Look up a file the way Windows would: ignoring case and accepting backslashes as
+    /// directory separators. Returns the actual pathname of the file if there is one.
+    pub fn get_case_insensitive(&self, key: &str) -> Option<&Path> {
+        let key = key.replace('\\', "/");
+        let key = key.strip_prefix('/').unwrap_or(&key);
+        self.filenames_lowercase
+            .get(&key.to_lowercase())
+            .and_then(|v| v.first())
+            .map(PathBuf::as_path)
+    }
+
+    /// Check a file reference that was not found as written.
+    ///
+    /// If the game would still find it on Windows, report the portability problem and return true.
+    /// Otherwise return false, so that the caller can report the file as missing.
+    fn check_portable_reference(&self, file: &str, token: &Token) -> bool {
+        let Some(actual) = self.get_case_insensitive(file) else {
+            return false;
+        };
+        let actual = actual.to_string_lossy();
+        self.mark_used(&actual);
+        if file.contains('\\') {
+            let msg = format!("file reference {file} uses backslashes");
+            let info = "this only works on Windows; use forward slashes `/` instead";
+            warn(ErrorKey::Portability).msg(msg).info(info).loc(token).push();
+        } else {
+            let msg = format!("file {file} only exists as {actual}");
+            let info = "this works on Windows, but not on Linux or Mac where file names are case-sensitive";
+            warn(ErrorKey::Portability).msg(msg).info(info).loc(token).push();
+        }
+        true
+    }
+
     pub fn entry_exists(&self, key: &str) -> bool {
         // file exists
         if self.exists(key) {
@@ -495,7 +565,7 @@ impl Fileset {
     #[cfg(feature = "ck3")] // vic3 happens not to use
     pub fn verify_exists(&self, file: &Token) {
         self.mark_used(&file.as_str().replace("//", "/"));
-        if !self.exists(file.as_str()) {
+        if !self.exists(file.as_str()) && !self.check_portable_reference(file.as_str(), file) {
             let msg = "referenced file does not exist";
             report(ErrorKey::MissingFile, Item::File.severity()).msg(msg).loc(file).push();
         }
@@ -503,7 +573,7 @@ impl Fileset {
 
     pub fn verify_exists_implied(&self, file: &str, t: &Token, max_sev: Severity) {
         self.mark_used(&file.replace("//", "/"));
-        if !self.exists(file) {
+        if !self.exists(file) && !self.check_portable_reference(file, t) {
             let msg = format!("file {file} does not exist");
             report(ErrorKey::MissingFile, Item::File.severity().at_most(max_sev))
                 .msg(msg)
@@ -514,7 +584,7 @@ impl Fileset {
 
     pub fn verify_exists_implied_crashes(&self, file: &str, t: &Token) {
         self.mark_used(&file.replace("//", "/"));
-        if !self.exists(file) {
+        if !self.exists(file) && !self.check_portable_reference(file, t) {
             let msg = format!("file {file} does not exist");
             fatal(ErrorKey::Crash).msg(msg).loc(t).push();
         }
@@ -577,6 +647,60 @@ impl Fileset {
             }
             warned.push(dirname);
         }
+
+        self.validate_portability();
+    }
+
+    /// Check the mod's own files for names that will cause trouble on some operating systems.
+    fn validate_portability(&self) {
+        for entry in &self.ordered_files {
+            if entry.kind < FileKind::LoadedMod(0) {
+                continue;
+            }
+            // Only the file name is checked, because on Windows the path has backslash separators
+            if entry.filename().to_string_lossy().contains('\\') {
+                let msg = "file name contains a backslash";
+                let info = "Windows will treat the backslash as a directory separator";
+                warn(ErrorKey::Portability).msg(msg).info(info).loc(entry).push();
+            }
+            let len = entry.fullpath.to_string_lossy().chars().count();
+            if len >= WINDOWS_MAX_PATH {
+                let msg = format!("full path of this file is {len} characters long");
+                let info = format!(
+                    "Windows may fail to load files whose full path is longer than {} characters",
+                    WINDOWS_MAX_PATH - 1
+                );
+                warn(ErrorKey::Portability).msg(msg).info(info).loc(entry).push();
+            }
+        }
+
+        let mut groups: Vec<_> =
+            self.filenames_lowercase.values().filter(|paths| paths.len() > 1).collect();
+        groups.sort();
+        for paths in groups {
+            let mut entries: Vec<&FileEntry> = paths
+                .iter()
+                .filter_map(|path| {
+                    // `ordered_files` is sorted by path so a binary search works
+                    self.ordered_files
+                        .binary_search_by(|entry| entry.path.as_path().cmp(path))
+                        .ok()
+                        .map(|idx| &self.ordered_files[idx])
+                })
+                .collect();
+            if entries.len() < 2 {
+                continue;
+            }
+            entries.sort_by_key(|entry| std::cmp::Reverse(entry.kind));
+            let msg = "file names differ only in capitalization";
+            let info =
+                "on Windows only one of these files will be loaded, on Linux and Mac both will";
+            let mut builder = warn(ErrorKey::Portability).msg(msg).info(info).loc(entries[0]);
+            for entry in &entries[1..] {
+                builder = builder.loc(*entry, "same name");
+            }
+            builder.push();
+        }
     }
 
     pub fn check_unused_dds(&self, _data: &Everything) {
@@ -592,7 +716,7 @@ impl Fileset {
         }
         let mut printed_header = false;
         for entry in vec {
-            if !printed_header && will_maybe_log(entry, ErrorKey::UnusedFile) {
+            if !printed_header && will_log_abbreviated(entry, ErrorKey::UnusedFile) {
                 warn_header(ErrorKey::UnusedFile, "Unused DDS files:\n");
                 printed_header = true;
             }
@@ -0,0 +1,192 @@
+//! Loader for the font files (`.ttf`, `.otf` and `.fnt`) under `fonts/`.
+//!
+//! Only the set of characters each font can display is extracted. It is used to check that the
+//! localization text can be rendered.
+
+use std::path::PathBuf;
+
+use fnv::{FnvHashMap, FnvHashSet};
+
+use crate::fileset::{FileEntry, FileHandler};
+use crate::report::{err, ErrorKey};
+use crate::vfs::read;
+
+fn be16(buffer: &[u8], offset: usize) -> Option<u16> {
+    let bytes = buffer.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn be32(buffer: &[u8], offset: usize) -> Option<u32> {
+    let bytes = buffer.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn le32(buffer: &[u8], offset: usize) -> Option<u32> {
+    let bytes = buffer.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// The characters covered by each loaded font file, indexed by pathname.
+#[derive(Clone, Debug, Default)]
+pub struct FontFiles {
+    glyphs: FnvHashMap<String, FnvHashSet<char>>,
+}
+
+impl FontFiles {
+    /// Return the characters that the font file at `path` can display, if it was loaded.
+    pub fn glyphs(&self, path: &str) -> Option<&FnvHashSet<char>> {
+        self.glyphs.get(path.strip_prefix('/').unwrap_or(path))
+    }
+
+    /// Extract the characters from the `cmap` table of a TrueType or OpenType font.
+    fn sfnt_glyphs(buffer: &[u8]) -> Option<FnvHashSet<char>> {
+        // A font collection; just use the first font in it.
+        let font = if buffer.starts_with(b"ttcf") { be32(buffer, 12)? as usize } else { 0 };
+
+        let num_tables = be16(buffer, font + 4)?;
+        let mut cmap = None;
+        for i in 0..usize::from(num_tables) {
+            let record = font + 12 + i * 16;
+            if buffer.get(record..record + 4)? == b"cmap" {
+                cmap = Some(be32(buffer, record + 8)? as usize);
+            }
+        }
+        let cmap = cmap?;
+
+        let mut glyphs = FnvHashSet::default();
+        let num_subtables = be16(buffer, cmap + 2)?;
+        for i in 0..usize::from(num_subtables) {
+            let record = cmap + 4 + i * 8;
+            let platform = be16(buffer, record)?;
+            let encoding = be16(buffer, record + 2)?;
+            // Only the Unicode mappings are of interest
+            if platform != 0 && !(platform == 3 && (encoding == 1 || encoding == 10)) {
+                continue;
+            }
+            let subtable = cmap + be32(buffer, record + 4)? as usize;
+            match be16(buffer, subtable)? {
+                4 => Self::cmap_format4(buffer, subtable, &mut glyphs)?,
+                12 => Self::cmap_format12(buffer, subtable, &mut glyphs)?,
+                _ => (),
+            }
+        }
+        Some(glyphs)
+    }
+
+    fn cmap_format4(buffer: &[u8], subtable: usize, glyphs: &mut FnvHashSet<char>) -> Option<()> {
+        let segments = usize::from(be16(buffer, subtable + 6)? / 2);
+        let end_codes = subtable + 14;
+        let start_codes = end_codes + segments * 2 + 2;
+        let id_deltas = start_codes + segments * 2;
+        let id_range_offsets = id_deltas + segments * 2;
+        for seg in 0..segments {
+            let end = be16(buffer, end_codes + seg * 2)?;
+            let start = be16(buffer, start_codes + seg * 2)?;
+            let delta = be16(buffer, id_deltas + seg * 2)?;
+            let range_offset_pos = id_range_offsets + seg * 2;
+            let range_offset = be16(buffer, range_offset_pos)?;
+            for c in start..=end {
+                if c == 0xffff {
+                    break;
+                }
+                let glyph = if range_offset == 0 {
+                    c.wrapping_add(delta)
+                } else {
+                    let pos =
+                        range_offset_pos + usize::from(range_offset) + usize::from(c - start) * 2;
+                    match be16(buffer, pos)? {
+                        0 => 0,
+                        glyph => glyph.wrapping_add(delta),
+                    }
+                };
+                if glyph != 0 {
+                    if let Some(c) = char::from_u32(u32::from(c)) {
+                        glyphs.insert(c);
+                    }
+                }
+            }
+        }
+        Some(())
+    }
+
+    fn cmap_format12(buffer: &[u8], subtable: usize, glyphs: &mut FnvHashSet<char>) -> Option<()> {
+        let num_groups = be32(buffer, subtable + 12)? as usize;
+        for i in 0..num_groups {
+            let group = subtable + 16 + i * 12;
+            let start = be32(buffer, group)?;
+            // Guard against corrupt files claiming huge ranges
+            let end = be32(buffer, group + 4)?.min(0x0010_ffff);
+            glyphs.extend((start..=end).filter_map(char::from_u32));
+        }
+        Some(())
+    }
+
+    /// Extract the characters from a bitmap font in the `BMFont` format, in text or binary format.
+    fn fnt_glyphs(buffer: &[u8]) -> Option<FnvHashSet<char>> {
+        let mut glyphs = FnvHashSet::default();
+        if let Some(mut blocks) = buffer.strip_prefix(b"BMF\x03") {
+            while blocks.len() >= 5 {
+                let block_type = blocks[0];
+                let size = le32(blocks, 1)? as usize;
+                let block = blocks.get(5..5 + size)?;
+                if block_type == 4 {
+                    // chars block: 20 bytes per char, starting with the char id
+                    for record in block.chunks_exact(20) {
+                        glyphs.extend(le32(record, 0).and_then(char::from_u32));
+                    }
+                }
+                blocks = &blocks[5 + size..];
+            }
+        } else {
+            let text = String::from_utf8_lossy(buffer);
+            for line in text.lines() {
+                let mut words = line.split_whitespace();
+                if words.next() != Some("char") {
+                    continue;
+                }
+                for word in words {
+                    if let Some(id) = word.strip_prefix("id=") {
+                        glyphs.extend(id.parse().ok().and_then(char::from_u32));
+                    }
+                }
+            }
+        }
+        Some(glyphs)
+    }
+}
+
+impl FileHandler<FnvHashSet<char>> for FontFiles {
+    fn subpath(&self) -> PathBuf {
+        PathBuf::from("fonts")
+    }
+
+    fn load_file(&self, entry: &FileEntry) -> Option<FnvHashSet<char>> {
+        let ext = entry.path().extension()?.to_string_lossy().to_lowercase();
+        let is_fnt = ext == "fnt";
+        if !is_fnt && ext != "ttf" && ext != "otf" {
+            return None;
+        }
+
+        match read(entry.fullpath()) {
+            Ok(buffer) => {
+                let glyphs =
+                    if is_fnt { Self::fnt_glyphs(&buffer) } else { Self::sfnt_glyphs(&buffer) };
+                if glyphs.is_none() {
+                    let msg = "could not find the character map in font file";
+                    err(ErrorKey::ReadError).msg(msg).loc(entry).push();
+                }
+                glyphs
+            }
+            Err(e) => {
+                let msg = "could not read font file";
+                let info = format!("{e:#}");
+                err(ErrorKey::ReadError).msg(msg).info(info).loc(entry).push();
+                None
+            }
+        }
+    }
+
+    fn handle_file(&mut self, entry: &FileEntry, glyphs: FnvHashSet<char>) {
+        self.glyphs.insert(entry.path().to_string_lossy().to_string(), glyphs);
+    }
+}
@@ -1,15 +1,30 @@
+use std::fmt::Write as _;
 use std::sync::Arc;
 
 use fnv::{FnvHashMap, FnvHashSet};
 
 use crate::block::{Block, BlockItem, Comparator, Eq::Single, Field, BV};
 use crate::data::gui::{GuiTemplate, GuiType};
+use crate::datatype::Datatype;
 use crate::everything::Everything;
-use crate::gui::validate::validate_property;
+use crate::gui::validate::{validate_property, GuiContext};
 use crate::gui::{BuiltinWidget, GuiValidation, PropertyContainer, WidgetProperty};
 use crate::lowercase::Lowercase;
 use crate::report::{err, untidy, warn, ErrorKey};
-use crate::token::Token;
+use crate::token::{Loc, Token};
+
+/// The properties that select a frame from a widget's texture.
+const FRAME_PROPERTIES: &[WidgetProperty] = &[
+    WidgetProperty::frame,
+    WidgetProperty::disableframe,
+    WidgetProperty::downframe,
+    WidgetProperty::downhoverframe,
+    WidgetProperty::downpressedframe,
+    WidgetProperty::overframe,
+    WidgetProperty::upframe,
+    WidgetProperty::uphoverframe,
+    WidgetProperty::uppressedframe,
+];
 
 /// An element of a [`GuiBlock`]
 #[derive(Debug, Clone)]
@@ -34,6 +49,8 @@ enum GuiItem {
 /// A processed version of a [`Block`] meant for `.gui` files.
 #[derive(Debug, Clone, Default)]
 pub struct GuiBlock {
+    /// Where the block was defined.
+    loc: Option<Loc>,
     /// The widget's ultimate base type or complex property type, if known.
     /// This determines which properties are valid in this block.
     container: Option<PropertyContainer>,
@@ -43,6 +60,10 @@ pub struct GuiBlock {
     items: Vec<GuiItem>,
     /// The names of all named blocks in this block, its base types, and its children.
     substnames: FnvHashSet<String>,
+    /// Whether this block, its base types, or its children contain widgets that were not
+    /// processed, such as template references and `recursive = yes` widgets.
+    /// If so, `substnames` may be incomplete.
+    opaque: bool,
 }
 
 /// An indication of where this [`Block`] was found, to help with determining the metadata for the
@@ -82,11 +103,15 @@ impl GuiBlock {
 
         // Blank slate to work on
         let mut gui = Self {
+            loc: Some(block.loc),
             container: None,
             base: None,
             items: Vec::new(),
             substnames: FnvHashSet::default(),
+            opaque: false,
         };
+        // Blockoverrides that did not find their block
+        let mut unmatched = Vec::new();
 
         // Fill in `container` and `base` fields if known
         match from {
@@ -96,7 +121,13 @@ impl GuiBlock {
                     gui.container = basetype.builtin(types).map(PropertyContainer::from);
                     let gui_block = basetype.gui_block(types, templates);
                     gui.substnames = gui_block.substnames.clone();
+                    gui.opaque = gui_block.opaque;
                     gui.base = Some(gui_block);
+                } else if BuiltinWidget::builtin_current_game(&Lowercase::new(base.as_str()))
+                    .is_none()
+                {
+                    // The base type is unknown, which is reported elsewhere
+                    gui.opaque = true;
                 }
             }
             GuiBlockFrom::PropertyKey(prop) => {
@@ -144,6 +175,7 @@ impl GuiBlock {
                                         types,
                                         templates,
                                     );
+                                    gui.extend_names(&guiblock);
                                     gui.items.push(GuiItem::ComplexProperty(
                                         prop,
                                         key.clone(),
@@ -166,6 +198,7 @@ impl GuiBlock {
                                             types,
                                             templates,
                                         );
+                                        gui.extend_names(&guiblock);
                                         gui.items.push(GuiItem::WidgetProperty(
                                             prop,
                                             key.clone(),
@@ -173,6 +206,7 @@ impl GuiBlock {
                                         ));
                                     }
                                     _ => {
+                                        gui.opaque = true;
                                         gui.items.push(GuiItem::Property(
                                             prop,
                                             key.clone(),
@@ -193,7 +227,7 @@ impl GuiBlock {
                                     types,
                                     templates,
                                 );
-                                gui.substnames.extend(guiblock.substnames.iter().cloned());
+                                gui.extend_names(&guiblock);
                                 gui.items.push(GuiItem::Widget(key_lc.into_owned(), guiblock));
                             }
                         } else if let Ok(builtin) = BuiltinWidget::try_from(&key_lc) {
@@ -237,37 +271,50 @@ impl GuiBlock {
                         let guiblock =
                             GuiBlock::from_block(GuiBlockFrom::NoParent, block, types, templates);
                         gui.substnames.insert(name.to_string());
-                        gui.substnames.extend(guiblock.substnames.iter().cloned());
+                        gui.extend_names(&guiblock);
                         gui.items.push(GuiItem::Subst(name.to_string(), guiblock));
                     }
                     state = Expecting::Field;
                 }
                 Expecting::BlockOverrideBody(name) => {
                     if let Some(block) = item.expect_block() {
+                        let guiblock =
+                            GuiBlock::from_block(GuiBlockFrom::NoParent, block, types, templates);
                         if gui.substnames.contains(name.as_str()) {
-                            let guiblock = GuiBlock::from_block(
-                                GuiBlockFrom::NoParent,
-                                block,
-                                types,
-                                templates,
-                            );
                             gui.apply_override(name, &guiblock);
                             gui.items.push(GuiItem::Override(name.clone(), guiblock));
-                        } else if !matches!(from, GuiBlockFrom::Template | GuiBlockFrom::NoParent) {
-                            // TODO: can't do this until we search Widget fields for overrides as well.
-                            // let msg = format!("did not find block for blockoverride `{name}`");
-                            // err(ErrorKey::Gui).msg(msg).loc(name).push();
+                        } else {
+                            // The block may be in a contained widget that comes later.
+                            unmatched.push((name, guiblock));
                         }
                     }
                     state = Expecting::Field;
                 }
             }
         }
+
+        for (name, guiblock) in unmatched {
+            if gui.substnames.contains(name.as_str()) {
+                gui.apply_override(name, &guiblock);
+            } else if matches!(from, GuiBlockFrom::Template | GuiBlockFrom::NoParent) {
+                // It may find its block after being inlined
+            } else if !gui.opaque {
+                let msg = format!("did not find block for blockoverride `{name}`");
+                err(ErrorKey::Gui).msg(msg).loc(name).push();
+            }
+            gui.items.push(GuiItem::Override(name.clone(), guiblock));
+        }
         Arc::new(gui)
     }
 
-    pub fn inline(&mut self, other: &Arc<GuiBlock>) {
+    /// Take note of the named blocks in a contained [`GuiBlock`].
+    fn extend_names(&mut self, other: &GuiBlock) {
         self.substnames.extend(other.substnames.iter().cloned());
+        self.opaque |= other.opaque;
+    }
+
+    pub fn inline(&mut self, other: &Arc<GuiBlock>) {
+        self.extend_names(other);
         for item in &other.items {
             if let GuiItem::Override(name, gui_block) = item {
                 self.apply_override(name, gui_block);
@@ -321,29 +368,366 @@ impl GuiBlock {
         gui.clone() // cloning the Arc
     }
 
+    /// Find the widget whose `name` property is `name`, in this block or its contents.
+    /// `key` is the widget type or property that this block belongs to.
+    /// Returns the found block and its key.
+    pub fn find_named<'a>(&'a self, key: &'a str, name: &str) -> Option<(&'a str, &'a GuiBlock)> {
+        if self.has_name(name) {
+            return Some((key, self));
+        }
+        if let Some(found) = self.base.as_ref().and_then(|base| base.find_named_child(name)) {
+            return Some(found);
+        }
+        self.find_named_child(name)
+    }
+
+    fn has_name(&self, name: &str) -> bool {
+        self.items.iter().any(|item| {
+            matches!(item, GuiItem::Property(WidgetProperty::name, _, BV::Value(value)) if value.is(name))
+        }) || self.base.as_ref().is_some_and(|base| base.has_name(name))
+    }
+
+    fn find_named_child(&self, name: &str) -> Option<(&str, &GuiBlock)> {
+        self.items.iter().find_map(|item| match item {
+            GuiItem::Widget(key, gui_block) => gui_block.find_named(key.as_str(), name),
+            GuiItem::WidgetProperty(_, key, gui_block) => gui_block.find_named(key.as_str(), name),
+            GuiItem::Subst(_, gui_block) => gui_block.find_named_child(name),
+            _ => None,
+        })
+    }
+
+    /// Like [`GuiBlock::dump`], but wrapped in a `header {` `}` pair.
+    pub fn dump_as(&self, header: &str, indent: usize, out: &mut String) {
+        dump_line(out, indent, &format!("{header} {{"), self.loc);
+        self.dump(indent + 1, out);
+        dump_line(out, indent, "}", None);
+    }
+
+    /// Write out the contents of this block after all types, templates and blockoverrides have
+    /// been applied, in `.gui` file syntax. Each line is annotated with where it came from.
+    /// Properties of a base type that are set again by the widget are commented out.
+    pub fn dump(&self, indent: usize, out: &mut String) {
+        self.dump_overridden(indent, out, &[]);
+    }
+
+    /// Like [`GuiBlock::dump`], but comment out the properties in `overridden`, which are set
+    /// again by a widget based on this block.
+    fn dump_overridden(&self, indent: usize, out: &mut String, overridden: &[WidgetProperty]) {
+        if let Some(base) = &self.base {
+            let mut overridden = overridden.to_vec();
+            self.collect_properties(&mut overridden);
+            base.dump_overridden(indent, out, &overridden);
+        }
+        for item in &self.items {
+            match item {
+                GuiItem::Property(prop, key, bv) => {
+                    let comment = if overridden.contains(prop) { "# " } else { "" };
+                    let text = format!("{comment}{key} = {}", dump_bv(bv));
+                    dump_line(out, indent, &text, Some(key.loc));
+                }
+                GuiItem::Widget(key, gui_block) => {
+                    gui_block.dump_as(&format!("{key} ="), indent, out);
+                }
+                GuiItem::ComplexProperty(_, key, gui_block)
+                | GuiItem::WidgetProperty(_, key, gui_block) => {
+                    gui_block.dump_as(&format!("{key} ="), indent, out);
+                }
+                GuiItem::Subst(_, gui_block) => gui_block.dump_overridden(indent, out, overridden),
+                // Already applied
+                GuiItem::Override(_, _) => (),
+            }
+        }
+    }
+
+    /// Add the properties that this block sets, not counting its base, to `props`.
+    fn collect_properties(&self, props: &mut Vec<WidgetProperty>) {
+        for item in &self.items {
+            match item {
+                GuiItem::Property(prop, _, _) => props.push(*prop),
+                GuiItem::Subst(_, gui_block) => gui_block.collect_properties(props),
+                _ => (),
+            }
+        }
+    }
+
     /// Validate the property fields of this [`GuiBlock`] and all its contents.
     ///
     /// `container` is extra information to be used if `self.container` is `None`.
-    pub fn validate(&self, container: Option<PropertyContainer>, data: &Everything) {
-        let container = self.container.or(container);
+    /// `context` holds the datacontexts of the widgets around this one.
+    pub fn validate(
+        &self,
+        container: Option<PropertyContainer>,
+        context: &GuiContext,
+        data: &Everything,
+    ) {
+        self.validate_in_context(container, context, data);
+        self.validate_next_states();
+        self.validate_texture_frames(data);
+        self.validate_effect_file(data);
+    }
+
+    /// Check that the `effectname` of this widget is defined in its `shaderfile`.
+    fn validate_effect_file(&self, data: &Everything) {
+        let Some(effect) =
+            self.get_inherited_property(WidgetProperty::effectname).and_then(BV::get_value)
+        else {
+            return;
+        };
+        let Some(file) =
+            self.get_inherited_property(WidgetProperty::shaderfile).and_then(BV::get_value)
+        else {
+            return;
+        };
+        if !file.is("") && !effect.starts_with("[") {
+            data.shaders.verify_effect_in_file(file, effect, data);
+        }
+    }
+
+    /// Check the `framesize`, frame numbers and sprite borders of this widget against the size of
+    /// its `texture`. This is only possible if the texture is a dds file.
+    fn validate_texture_frames(&self, data: &Everything) {
+        let Some(texture) =
+            self.get_inherited_property(WidgetProperty::texture).and_then(BV::get_value)
+        else {
+            return;
+        };
+        let Some((width, height)) = data.dds.get_size(texture.as_str()) else {
+            return;
+        };
+        // A broken dds header can give a zero size, which leaves nothing to check against
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let (mut frame_width, mut frame_height) = (width, height);
+        if let Some(BV::Block(block)) = self.get_inherited_property(WidgetProperty::framesize) {
+            let sizes: Vec<_> = block.iter_values().map(Token::get_integer).collect();
+            if let [Some(w), Some(h)] = sizes[..] {
+                let (Ok(w), Ok(h)) = (u32::try_from(w), u32::try_from(h)) else {
+                    return;
+                };
+                if w == 0 || h == 0 {
+                    return;
+                }
+                if width % w != 0 || height % h != 0 {
+                    let msg = format!(
+                        "framesize {w}x{h} does not divide the texture size of {width}x{height}"
+                    );
+                    warn(ErrorKey::ImageFormat).msg(msg).loc(block).loc(texture, "texture").push();
+                }
+                (frame_width, frame_height) = (w, h);
+            }
+        }
+
+        // Frames are numbered from 1
+        let frames = i64::from(width / frame_width) * i64::from(height / frame_height);
+        for prop in FRAME_PROPERTIES {
+            if let Some(frame) = self.get_inherited_property(*prop).and_then(BV::get_value) {
+                if frame.get_integer().is_some_and(|n| n > frames) {
+                    let msg = format!("{prop} {frame} is out of range");
+                    let info =
+                        format!("the texture has {frames} frames of {frame_width}x{frame_height}");
+                    warn(ErrorKey::ImageFormat)
+                        .msg(msg)
+                        .info(info)
+                        .loc(frame)
+                        .loc(texture, "texture")
+                        .push();
+                }
+            }
+        }
+
+        let fits = |value: f64, size: u32| value <= f64::from(size);
+        if let Some(BV::Block(block)) = self.get_inherited_property(WidgetProperty::spriteborder) {
+            let borders: Vec<_> = block.iter_values().map(Token::get_number).collect();
+            if let [Some(x), Some(y)] = borders[..] {
+                if !fits(2.0 * x, frame_width) || !fits(2.0 * y, frame_height) {
+                    let msg = format!(
+                        "spriteborder does not fit inside the {frame_width}x{frame_height} texture"
+                    );
+                    warn(ErrorKey::ImageFormat).msg(msg).loc(block).loc(texture, "texture").push();
+                }
+            }
+        }
+        let sides: Vec<_> = [
+            WidgetProperty::spriteborder_left,
+            WidgetProperty::spriteborder_right,
+            WidgetProperty::spriteborder_top,
+            WidgetProperty::spriteborder_bottom,
+        ]
+        .into_iter()
+        .map(|prop| self.get_inherited_property(prop).and_then(BV::get_value))
+        .collect();
+        let border = |i: usize| sides[i].and_then(Token::get_number).unwrap_or(0.0);
+        if !fits(border(0) + border(1), frame_width) || !fits(border(2) + border(3), frame_height) {
+            let msg = format!(
+                "spriteborder sides do not fit inside the {frame_width}x{frame_height} texture"
+            );
+            // At least one of the sides must be set to get here
+            let side = sides.iter().flatten().next().unwrap();
+            warn(ErrorKey::ImageFormat).msg(msg).loc(*side).loc(texture, "texture").push();
+        }
+    }
+
+    /// Check that the `next` fields of this widget's states name other states of this widget.
+    fn validate_next_states(&self) {
+        let mut states = Vec::new();
+        self.collect_states(&mut states);
+        for state in &states {
+            if let Some(next) = state.get_property_value(WidgetProperty::next) {
+                let exists = states.iter().any(|state| {
+                    state
+                        .get_property_value(WidgetProperty::name)
+                        .is_some_and(|name| name.is(next.as_str()))
+                });
+                if !exists {
+                    let msg = format!("no state named `{next}` in this widget");
+                    warn(ErrorKey::Gui).msg(msg).loc(next).push();
+                }
+            }
+        }
+    }
+
+    /// Collect the `state` blocks of this widget, including the ones from its base type.
+    fn collect_states<'a>(&'a self, states: &mut Vec<&'a GuiBlock>) {
         if let Some(base) = &self.base {
-            base.validate(container, data);
+            base.collect_states(states);
         }
+        for item in &self.items {
+            match item {
+                GuiItem::ComplexProperty(WidgetProperty::state, _, gui_block) => {
+                    states.push(gui_block);
+                }
+                GuiItem::Subst(_, gui_block) => gui_block.collect_states(states),
+                _ => (),
+            }
+        }
+    }
 
+    /// Return the value of the last `prop` field in this block, if it has one.
+    fn get_property_value(&self, prop: WidgetProperty) -> Option<&Token> {
+        self.get_property(prop).and_then(BV::get_value)
+    }
+
+    /// Return the last `prop` field in this block, if it has one.
+    fn get_property(&self, prop: WidgetProperty) -> Option<&BV> {
+        self.items.iter().rev().find_map(|item| match item {
+            GuiItem::Property(p, _, bv) if *p == prop => Some(bv),
+            GuiItem::Subst(_, gui_block) => gui_block.get_property(prop),
+            _ => None,
+        })
+    }
+
+    /// Like [`GuiBlock::get_property`], but also look in the base types of this widget.
+    fn get_inherited_property(&self, prop: WidgetProperty) -> Option<&BV> {
+        self.get_property(prop)
+            .or_else(|| self.base.as_ref().and_then(|base| base.get_inherited_property(prop)))
+    }
+
+    /// Like [`GuiBlock::validate`], but return the datacontexts that this block's contents were
+    /// validated with. This lets a widget see the datacontext that was set by its base type.
+    fn validate_in_context(
+        &self,
+        container: Option<PropertyContainer>,
+        context: &GuiContext,
+        data: &Everything,
+    ) -> GuiContext {
+        let context = self.resolve_datacontext(container, context, data);
+        self.validate_items(container, &context, data);
+        context
+    }
+
+    /// Validate the `datacontext` properties of this widget and its base types, and return the
+    /// resulting datacontexts. The base types are resolved first, so that this widget's own
+    /// datacontext is applied on top of the one set by its base.
+    fn resolve_datacontext(
+        &self,
+        container: Option<PropertyContainer>,
+        context: &GuiContext,
+        data: &Everything,
+    ) -> GuiContext {
+        let container = self.container.or(container);
+        let mut context = match &self.base {
+            Some(base) => base.resolve_datacontext(container, context, data),
+            None => context.clone(),
+        };
+        for item in &self.items {
+            if let GuiItem::Property(prop @ WidgetProperty::datacontext, key, bv) = item {
+                if let Some(dtype) = validate_property(*prop, container, key, bv, &context, data) {
+                    context = context.with_datacontext(dtype, bv);
+                }
+            }
+        }
+        context
+    }
+
+    /// Validate everything except the `datacontext` properties in this widget and its base types.
+    /// The datacontext applies to the whole widget, including the items that came from its base
+    /// types, so `context` should be the one from [`GuiBlock::resolve_datacontext`].
+    fn validate_items(
+        &self,
+        container: Option<PropertyContainer>,
+        context: &GuiContext,
+        data: &Everything,
+    ) {
+        let container = self.container.or(container);
+        if let Some(base) = &self.base {
+            base.validate_items(container, context, data);
+        }
         for item in &self.items {
             match item {
+                GuiItem::Property(WidgetProperty::datacontext, _, _) | GuiItem::Override(_, _) => {}
                 GuiItem::Property(prop, key, bv) => {
-                    validate_property(*prop, container, key, bv, data);
+                    validate_property(*prop, container, key, bv, context, data);
                 }
                 GuiItem::Subst(_, gui_block) => {
-                    gui_block.validate(container, data);
+                    gui_block.validate_in_context(container, context, data);
+                }
+                GuiItem::WidgetProperty(WidgetProperty::item, _, gui_block) => {
+                    // The items of a datamodel get the model's elements as their datacontext,
+                    // and we don't know the element types of datamodels.
+                    gui_block.validate(None, &context.with(Datatype::Unknown), data);
                 }
                 GuiItem::Widget(_, gui_block)
                 | GuiItem::ComplexProperty(_, _, gui_block)
                 | GuiItem::WidgetProperty(_, _, gui_block) => {
-                    gui_block.validate(None, data);
+                    gui_block.validate(None, context, data);
                 }
-                GuiItem::Override(_, _) => (),
+            }
+        }
+    }
+}
+
+fn dump_line(out: &mut String, indent: usize, text: &str, loc: Option<Loc>) {
+    let indent = "    ".repeat(indent);
+    if let Some(loc) = loc {
+        let from = format!("{}:{} ({:?})", loc.pathname().display(), loc.line, loc.kind);
+        _ = writeln!(out, "{indent}{text}  # {from}");
+    } else {
+        _ = writeln!(out, "{indent}{text}");
+    }
+}
+
+fn dump_bv(bv: &BV) -> String {
+    match bv {
+        BV::Value(value) => {
+            let plain = !value.as_str().is_empty()
+                && value.as_str().chars().all(|c| c.is_alphanumeric() || "_.-|".contains(c));
+            if plain {
+                value.to_string()
+            } else {
+                format!("\"{value}\"")
+            }
+        }
+        BV::Block(block) => {
+            let values: Option<Vec<&Token>> =
+                block.iter_items().map(BlockItem::get_value).collect();
+            match values {
+                Some(values) if !values.is_empty() => {
+                    let values: Vec<String> = values.iter().map(ToString::to_string).collect();
+                    format!("{{ {} }}", values.join(" "))
+                }
+                _ => "{ ... }".to_string(),
             }
         }
     }
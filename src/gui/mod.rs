@@ -4,6 +4,7 @@ pub use self::block::{GuiBlock, GuiBlockFrom};
 pub use self::builtins::BuiltinWidget;
 pub use self::categories::GuiCategories;
 pub use self::properties::{GuiValidation, PropertyContainer, WidgetProperty};
+pub use self::validate::GuiContext;
 
 mod block;
 mod builtins;
@@ -81,6 +81,8 @@ pub enum GuiValidation {
     Choice(&'static [&'static str]),
     /// A `|`-separated list of strings from the array given here.
     ChoiceSet(&'static [&'static str]),
+    /// The name of a `state` in the same widget. This is checked by the widget's [`GuiBlock`].
+    StateName,
     /// A block containing one widget, or the name of a template containing one widget.
     Widget,
     /// A string containing a localization text format specifier starting with `#`
@@ -497,12 +499,12 @@ impl GuiValidation {
             drag_drop_args => CString,
             drag_drop_base_type => Choice(&["icon", "coat_of_arms_icon"]),
             drag_drop_data => Datacontext,
-            drag_drop_id => UncheckedValue, // TODO what are the options?
+            drag_drop_id => UncheckedValue, // a free-form id that pairs drag sources with drop targets
             draggable_by => MouseButtonSet(&["left", "right", "middle"]),
             droptarget => Boolean,
             duration => Number,
             effect => DatatypeExpr,
-            effectname => UncheckedValue, // TODO validate effect names
+            effectname => Item(Item::ShaderEffect),
             elide => Choice(&["right", "middle", "left"]),
             enabled => Boolean,
             end_sound => ComplexProperty,
@@ -521,14 +523,14 @@ impl GuiValidation {
             fontsize => Integer,
             fontsize_min => Integer,
             fonttintcolor => Color,
-            fontweight => UncheckedValue, // TODO: what are the options?
+            fontweight => UncheckedValue, // TODO: only example is "bold"
             force_data_properties_update => Boolean,
             format_override => FormatOverride,
             frame => Integer,
             framesize => CVector2i,
             from => CVector2f,
             gfx_environment_file => Item(Item::File),
-            gfxtype => UncheckedValue, // TODO: what are the options?
+            gfxtype => UncheckedValue, // TODO: only example is "icongfx"
             glow => ComplexProperty,
             glow_alpha => Number,
             glow_alpha_mask => Integer,
@@ -597,7 +599,7 @@ impl GuiValidation {
             movable => Boolean,
             multiline => Boolean,
             name => UncheckedValue,
-            next => UncheckedValue, // TODO: choices are states in the same widget
+            next => StateName,
             noprogresstexture => Item(Item::File),
             odd_row_widget => Widget,
             on_finish => DatatypeExpr,
@@ -691,7 +693,7 @@ impl GuiValidation {
             spriteborder_left => Integer,
             spriteborder_right => Integer,
             spriteborder_top => Integer,
-            spritetype => UncheckedValue, // TODO
+            spritetype => UncheckedValue, // TODO: only example is "corneredTiled"
             stackmode => UncheckedValue,  // TODO only example is "top"
             start_sound => ComplexProperty,
             state => ComplexProperty,
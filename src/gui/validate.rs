@@ -1,7 +1,15 @@
 use crate::block::{Block, BV};
 use crate::context::ScopeContext;
-use crate::data::localization::LocaValue;
-use crate::datatype::{validate_datatypes, Datatype};
+use crate::data::localization::{markup_formats, LocaValue};
+#[cfg(feature = "ck3")]
+use crate::datatype::Ck3Datatype;
+#[cfg(feature = "imperator")]
+use crate::datatype::ImperatorDatatype;
+#[cfg(feature = "vic3")]
+use crate::datatype::Vic3Datatype;
+use crate::datatype::{
+    validate_datatypes, validate_scripted_gui_call, CodeArg, CodeChain, Datatype,
+};
 use crate::everything::Everything;
 #[cfg(feature = "ck3")]
 use crate::game::Game;
@@ -11,24 +19,162 @@ use crate::gui::{GuiCategories, GuiValidation, PropertyContainer, WidgetProperty
 use crate::helpers::stringify_choices;
 use crate::item::Item;
 use crate::parse::localization::ValueParser;
-use crate::report::{err, warn, ErrorKey, Severity};
+use crate::report::{err, report, untidy, warn, ErrorKey, Severity};
 use crate::scopes::Scopes;
 use crate::token::Token;
 use crate::validator::Validator;
 
+/// The datatypes that can be used where a number is expected.
+const NUMBER_DATATYPES: &[Datatype] = &[
+    Datatype::CFixedPoint,
+    Datatype::double,
+    Datatype::float,
+    Datatype::int8,
+    Datatype::int16,
+    Datatype::int32,
+    Datatype::int64,
+    Datatype::uint8,
+    Datatype::uint16,
+    Datatype::uint32,
+    Datatype::uint64,
+];
+
+/// The datatypes that can be used where a string, such as an item key, is expected.
+const STRING_DATATYPES: &[Datatype] = &[Datatype::CString, Datatype::CUTF8String];
+
+/// The datatypes of the `datacontext` properties of a widget and the widgets that contain it.
+/// `[Character.GetName]` style expressions look up their first element in these datacontexts.
+///
+/// A datacontext whose type could not be determined is stored as `Datatype::Unknown`.
+#[derive(Debug, Clone, Default)]
+pub struct GuiContext {
+    types: Vec<Datatype>,
+    /// The name of the scripted gui in the innermost `ScriptedGui` datacontext, if it is known.
+    scripted_gui: Option<Token>,
+}
+
+impl GuiContext {
+    /// Return a copy of this context with `dtype` as the innermost datacontext.
+    pub fn with(&self, dtype: Datatype) -> Self {
+        let mut types = self.types.clone();
+        types.push(dtype);
+        Self { types, scripted_gui: self.scripted_gui.clone() }
+    }
+
+    /// Like [`GuiContext::with`], but also remember the scripted gui if `bv` is a
+    /// `[GetScriptedGui('name')]` datacontext.
+    pub fn with_datacontext(&self, dtype: Datatype, bv: &BV) -> Self {
+        let mut context = self.with(dtype);
+        if is_scripted_gui_datatype(dtype) {
+            context.scripted_gui = bv.get_value().and_then(scripted_gui_name);
+        }
+        context
+    }
+
+    /// Warn if `chain`, or a chain in its arguments, starts with a datatype that is not provided
+    /// by any of the datacontexts here.
+    ///
+    /// This is only done if the innermost datacontext is known, and even then the game may have
+    /// given an outer widget a datacontext of the right type, so the report is weak.
+    ///
+    /// Calls on the `ScriptedGui` datacontext are checked against the scripted gui's definition.
+    fn check_chain(&self, chain: &CodeChain, data: &Everything) {
+        for (i, code) in chain.codes.iter().enumerate() {
+            if i == 0 {
+                self.check_first(&code.name);
+                if let (true, Some(sgui), Some(next)) =
+                    (code.name.is("ScriptedGui"), &self.scripted_gui, chain.codes.get(1))
+                {
+                    let mut sc = ScopeContext::new(Scopes::None, &code.name);
+                    validate_scripted_gui_call(sgui, next, data, &mut sc, "");
+                }
+            }
+            for arg in &code.arguments {
+                if let CodeArg::Chain(chain) = arg {
+                    self.check_chain(chain, data);
+                }
+            }
+        }
+    }
+
+    fn check_first(&self, name: &Token) {
+        let Some(&innermost) = self.types.last() else {
+            return;
+        };
+        if !is_game_datatype(innermost) {
+            return;
+        }
+        if let Ok(dtype) = name.as_str().parse::<Datatype>() {
+            if is_game_datatype(dtype) && !self.types.contains(&dtype) {
+                let msg = format!("no {dtype} datacontext here");
+                let info = format!("the datacontext here is {innermost}; this only works if the game gives an outer widget a {dtype} datacontext");
+                warn(ErrorKey::Datafunctions).weak().msg(msg).info(info).loc(name).push();
+            }
+        }
+    }
+}
+
+/// Is `dtype` one of the game's own object types, as opposed to a generic type like `CString`
+/// or one of our synthetic types?
+fn is_game_datatype(dtype: Datatype) -> bool {
+    match dtype {
+        #[cfg(feature = "ck3")]
+        Datatype::Ck3(_) => true,
+        #[cfg(feature = "vic3")]
+        Datatype::Vic3(_) => true,
+        #[cfg(feature = "imperator")]
+        Datatype::Imperator(_) => true,
+        _ => false,
+    }
+}
+
+fn is_scripted_gui_datatype(dtype: Datatype) -> bool {
+    match dtype {
+        #[cfg(feature = "ck3")]
+        Datatype::Ck3(Ck3Datatype::ScriptedGui) => true,
+        #[cfg(feature = "vic3")]
+        Datatype::Vic3(Vic3Datatype::ScriptedGui) => true,
+        #[cfg(feature = "imperator")]
+        Datatype::Imperator(ImperatorDatatype::ScriptedGui) => true,
+        _ => false,
+    }
+}
+
+/// Return the name of the scripted gui in a `[GetScriptedGui('name')]` expression.
+fn scripted_gui_name(value: &Token) -> Option<Token> {
+    let valuevec = ValueParser::new(vec![value]).parse_value();
+    let [LocaValue::Code(chain, _)] = &valuevec[..] else {
+        return None;
+    };
+    let [code] = &chain.codes[..] else {
+        return None;
+    };
+    if !code.name.is("GetScriptedGui") {
+        return None;
+    }
+    match code.arguments.first() {
+        Some(CodeArg::Literal(name)) => Some(name.clone()),
+        _ => None,
+    }
+}
+
+/// Validate a property of a widget, in the datacontext given by `context`.
+///
+/// Returns the datatype of the property's expression if it is a datacontext property.
 pub fn validate_property(
     property: WidgetProperty,
     container: Option<PropertyContainer>,
     key: &Token,
     bv: &BV,
+    context: &GuiContext,
     data: &Everything,
-) {
+) -> Option<Datatype> {
     let game = GameFlags::game();
     let gameflags = property.to_game_flags();
     if !gameflags.contains(game) {
         let msg = format!("{key} is only for {gameflags}");
         err(ErrorKey::WrongGame).weak().msg(msg).loc(key).push();
-        return;
+        return None;
     }
     if let Some(container) = container {
         let allowed_properties = match container {
@@ -46,20 +192,30 @@ pub fn validate_property(
         }
     }
     match GuiValidation::from_property(property) {
-        GuiValidation::UncheckedValue | GuiValidation::Format => {
-            // TODO: validate Format as a format string
+        GuiValidation::UncheckedValue | GuiValidation::StateName => {
             _ = bv.expect_value();
         }
+        GuiValidation::Format => {
+            if let Some(value) = bv.expect_value() {
+                if value.starts_with("#") {
+                    // A default format is left open for the text that follows it
+                    validate_gui_text(key, value, false, context, data);
+                } else {
+                    let msg = format!("{key} should start with `#`");
+                    warn(ErrorKey::Markup).msg(msg).loc(value).push();
+                }
+            }
+        }
         GuiValidation::DatatypeExpr | GuiValidation::Datamodel => {
-            validate_datatype_field(Datatype::Unknown, key, bv, data, false);
+            validate_datatype_field(Datatype::Unknown, key, bv, context, data, false);
         }
         GuiValidation::Datacontext => {
-            validate_datatype_field(Datatype::Unknown, key, bv, data, true);
+            return Some(validate_datatype_field(Datatype::Unknown, key, bv, context, data, true));
         }
         GuiValidation::Boolean => {
             if let Some(value) = bv.expect_value() {
                 if value.starts_with("[") {
-                    validate_datatype_field(Datatype::bool, key, bv, data, false);
+                    validate_datatype_field(Datatype::bool, key, bv, context, data, false);
                 } else if !value.lowercase_is("yes") && !value.lowercase_is("no") {
                     // TODO: decide based on the field name whether to upgrade to error?
                     warn(ErrorKey::Validation).msg("expected yes or no").loc(value).push();
@@ -87,7 +243,7 @@ pub fn validate_property(
         GuiValidation::Integer => {
             if let Some(value) = bv.expect_value() {
                 if value.starts_with("[") {
-                    validate_datatype_field(Datatype::int32, key, bv, data, false);
+                    validate_datatype_field(Datatype::int32, key, bv, context, data, false);
                 } else {
                     value.expect_integer();
                 }
@@ -96,7 +252,7 @@ pub fn validate_property(
         GuiValidation::UnsignedInteger => {
             if let Some(value) = bv.expect_value() {
                 if value.starts_with("[") {
-                    validate_datatype_field(Datatype::uint32, key, bv, data, false);
+                    validate_datatype_field(Datatype::uint32, key, bv, context, data, false);
                 } else if let Some(i) = value.expect_integer() {
                     if i < 0 {
                         let msg = format!("{key} needs an unsigned integer");
@@ -108,7 +264,7 @@ pub fn validate_property(
         GuiValidation::Number => {
             if let Some(value) = bv.expect_value() {
                 if value.starts_with("[") {
-                    validate_datatype_field(Datatype::float, key, bv, data, false);
+                    validate_datatype_field(Datatype::float, key, bv, context, data, false);
                 } else {
                     value.expect_number();
                 }
@@ -117,8 +273,14 @@ pub fn validate_property(
         GuiValidation::NumberOrInt32 => {
             if let Some(value) = bv.expect_value() {
                 if value.starts_with("[") {
-                    // TODO: need a way to express it can be int32 or float
-                    validate_datatype_field(Datatype::Unknown, key, bv, data, false);
+                    validate_datatype_field_of(
+                        NUMBER_DATATYPES,
+                        "a number",
+                        key,
+                        bv,
+                        context,
+                        data,
+                    );
                 } else {
                     value.expect_number();
                 }
@@ -127,8 +289,14 @@ pub fn validate_property(
         GuiValidation::NumberF => {
             if let Some(value) = bv.expect_value() {
                 if value.starts_with("[") {
-                    // TODO: need a way to express it can be int32 or float
-                    validate_datatype_field(Datatype::float, key, bv, data, false);
+                    validate_datatype_field_of(
+                        NUMBER_DATATYPES,
+                        "a number",
+                        key,
+                        bv,
+                        context,
+                        data,
+                    );
                 } else if let Some(value) = value.strip_suffix("f") {
                     // TODO: this f is used in vanilla; check it really works.
                     value.expect_number();
@@ -140,8 +308,14 @@ pub fn validate_property(
         GuiValidation::NumberOrPercent => {
             if let Some(value) = bv.expect_value() {
                 if value.starts_with("[") {
-                    // TODO: need a way to express it can be int32 or float
-                    validate_datatype_field(Datatype::Unknown, key, bv, data, false);
+                    validate_datatype_field_of(
+                        NUMBER_DATATYPES,
+                        "a number",
+                        key,
+                        bv,
+                        context,
+                        data,
+                    );
                 } else if let Some(value) = value.strip_suffix("%") {
                     value.expect_number();
                 } else {
@@ -151,7 +325,7 @@ pub fn validate_property(
         }
         GuiValidation::TwoNumberOrPercent => match bv {
             BV::Value(_) => {
-                validate_datatype_field(Datatype::CVector2f, key, bv, data, false);
+                validate_datatype_field(Datatype::CVector2f, key, bv, context, data, false);
             }
             BV::Block(block) => {
                 for value in block.iter_values_warn() {
@@ -165,7 +339,7 @@ pub fn validate_property(
         },
         GuiValidation::CVector2f => match bv {
             BV::Value(_) => {
-                validate_datatype_field(Datatype::CVector2f, key, bv, data, false);
+                validate_datatype_field(Datatype::CVector2f, key, bv, context, data, false);
             }
             BV::Block(block) => {
                 let mut vd = Validator::new(block, data);
@@ -175,7 +349,7 @@ pub fn validate_property(
         },
         GuiValidation::CVector2i => match bv {
             BV::Value(_) => {
-                validate_datatype_field(Datatype::CVector2i, key, bv, data, false);
+                validate_datatype_field(Datatype::CVector2i, key, bv, context, data, false);
             }
             BV::Block(block) => {
                 let mut vd = Validator::new(block, data);
@@ -185,7 +359,7 @@ pub fn validate_property(
         },
         GuiValidation::CVector3f => match bv {
             BV::Value(_) => {
-                validate_datatype_field(Datatype::CVector3f, key, bv, data, false);
+                validate_datatype_field(Datatype::CVector3f, key, bv, context, data, false);
             }
             BV::Block(block) => {
                 let mut vd = Validator::new(block, data);
@@ -195,7 +369,7 @@ pub fn validate_property(
         },
         GuiValidation::CVector4f => match bv {
             BV::Value(_) => {
-                validate_datatype_field(Datatype::CVector4f, key, bv, data, false);
+                validate_datatype_field(Datatype::CVector4f, key, bv, context, data, false);
             }
             BV::Block(block) => {
                 let mut vd = Validator::new(block, data);
@@ -205,21 +379,27 @@ pub fn validate_property(
         },
         GuiValidation::Color => match bv {
             BV::Value(_) => {
-                // TODO: can be CVector4f or CString
-                validate_datatype_field(Datatype::Unknown, key, bv, data, false);
+                let dtypes = &[Datatype::CVector4f, Datatype::CString];
+                validate_datatype_field_of(dtypes, "a color", key, bv, context, data);
             }
             BV::Block(block) => {
                 validate_gui_color(block, data);
             }
         },
         GuiValidation::CString => {
-            validate_datatype_field(Datatype::CString, key, bv, data, false);
+            validate_datatype_field(Datatype::CString, key, bv, context, data, false);
         }
         GuiValidation::Item(itype) => {
             if let Some(value) = bv.expect_value() {
                 if value.starts_with("[") {
-                    // TODO: need some way of specifying "stringable" datatypes
-                    validate_datatype_field(Datatype::Unknown, key, bv, data, false);
+                    validate_datatype_field_of(
+                        STRING_DATATYPES,
+                        "a string",
+                        key,
+                        bv,
+                        context,
+                        data,
+                    );
                 } else {
                     data.verify_exists(itype, value);
                 }
@@ -228,8 +408,14 @@ pub fn validate_property(
         GuiValidation::ItemOrBlank(itype) => {
             if let Some(value) = bv.expect_value() {
                 if value.starts_with("[") {
-                    // TODO: need some way of specifying "stringable" datatypes
-                    validate_datatype_field(Datatype::Unknown, key, bv, data, false);
+                    validate_datatype_field_of(
+                        STRING_DATATYPES,
+                        "a string",
+                        key,
+                        bv,
+                        context,
+                        data,
+                    );
                 } else if !value.is("") {
                     data.verify_exists(itype, value);
                 }
@@ -250,8 +436,14 @@ pub fn validate_property(
                 // TODO: datatype is only really used by button_ignore.
                 // Is it valid for the others?
                 if value.starts_with("[") {
-                    // TODO: need some way of specifying "stringable" datatypes
-                    validate_datatype_field(Datatype::Unknown, key, bv, data, false);
+                    validate_datatype_field_of(
+                        STRING_DATATYPES,
+                        "a string",
+                        key,
+                        bv,
+                        context,
+                        data,
+                    );
                 } else {
                     let value_lc = value.as_str().to_lowercase();
                     if !choices.contains(&&*value_lc) {
@@ -331,10 +523,7 @@ pub fn validate_property(
         }
         GuiValidation::RawText => {
             if let Some(value) = bv.expect_value() {
-                let valuevec = ValueParser::new(vec![value]).parse_value();
-                for v in valuevec {
-                    validate_gui_loca(key, v, data);
-                }
+                validate_gui_text(key, value, true, context, data);
                 if !value.starts_with("[") {
                     // raw text can still be a localization key sometimes
                     data.mark_used(Item::Localization, value.as_str());
@@ -343,25 +532,45 @@ pub fn validate_property(
         }
         GuiValidation::Text => {
             if let Some(value) = bv.expect_value() {
-                let valuevec = ValueParser::new(vec![value]).parse_value();
-                for v in valuevec {
-                    validate_gui_loca(key, v, data);
-                }
+                validate_gui_text(key, value, true, context, data);
                 if !value.starts_with("[") && !value.as_str().contains(' ') {
                     data.verify_exists(Item::Localization, value);
                 }
             }
         }
     }
+    None
 }
 
+/// Validate a field that should be a single `[ ]` expression returning one of `dtypes`.
+/// `what` describes those types for the error message.
+fn validate_datatype_field_of(
+    dtypes: &[Datatype],
+    what: &str,
+    key: &Token,
+    bv: &BV,
+    context: &GuiContext,
+    data: &Everything,
+) {
+    let dtype = validate_datatype_field(Datatype::Unknown, key, bv, context, data, false);
+    if dtype != Datatype::Unknown && !dtypes.contains(&dtype) {
+        if let Some(value) = bv.get_value() {
+            let msg = format!("this returns {dtype} but {what} is needed here");
+            warn(ErrorKey::Datafunctions).msg(msg).loc(value).push();
+        }
+    }
+}
+
+/// Validate a field that should be a single `[ ]` expression, and return the datatype of that
+/// expression if it is known.
 fn validate_datatype_field(
     dtype: Datatype,
     key: &Token,
     bv: &BV,
+    context: &GuiContext,
     data: &Everything,
     allow_promote: bool,
-) {
+) -> Datatype {
     if let Some(value) = bv.expect_value() {
         if value.starts_with("[") {
             let valuevec = ValueParser::new(vec![value]).parse_value();
@@ -370,7 +579,8 @@ fn validate_datatype_field(
                 match &valuevec[0] {
                     // TODO: validate format
                     LocaValue::Code(chain, format) => {
-                        validate_datatypes(
+                        context.check_chain(chain, data);
+                        return validate_datatypes(
                             chain,
                             data,
                             &mut sc,
@@ -395,13 +605,44 @@ fn validate_datatype_field(
             warn(ErrorKey::Validation).msg(msg).loc(value).push();
         }
     }
+    Datatype::Unknown
 }
 
-fn validate_gui_loca(key: &Token, loca_value: LocaValue, data: &Everything) {
+/// Validate gui text the way a localization value is validated: its `[ ]` code, `#markup` and
+/// `@icons!`. If `closed` is true, every `#markup` should be closed with `#!` before the end.
+fn validate_gui_text(
+    key: &Token,
+    value: &Token,
+    closed: bool,
+    context: &GuiContext,
+    data: &Everything,
+) {
+    let mut open = Vec::new();
+    for v in ValueParser::new(vec![value]).parse_value() {
+        match v {
+            LocaValue::Markup(ref markup) => open.push(markup.clone()),
+            // This pops the innermost open markup, if there is one
+            LocaValue::MarkupEnd(ref end) if open.pop().is_none() => {
+                let msg = "`#!` without a `#markup` to close";
+                warn(ErrorKey::Markup).msg(msg).loc(end).push();
+            }
+            _ => (),
+        }
+        validate_gui_loca(key, v, context, data);
+    }
+    if closed {
+        if let Some(markup) = open.first() {
+            let msg = format!("`{markup}` is not closed with `#!`");
+            untidy(ErrorKey::Markup).msg(msg).loc(markup).push();
+        }
+    }
+}
+
+fn validate_gui_loca(key: &Token, loca_value: LocaValue, context: &GuiContext, data: &Everything) {
     match loca_value {
         LocaValue::Concat(v) => {
             for loca_value in v {
-                validate_gui_loca(key, loca_value, data);
+                validate_gui_loca(key, loca_value, context, data);
             }
         }
         LocaValue::Code(chain, format) => {
@@ -418,6 +659,7 @@ fn validate_gui_loca(key: &Token, loca_value: LocaValue, data: &Everything) {
                 }
             }
 
+            context.check_chain(&chain, data);
             let mut sc = ScopeContext::new(Scopes::None, key);
             validate_datatypes(
                 &chain,
@@ -429,6 +671,24 @@ fn validate_gui_loca(key: &Token, loca_value: LocaValue, data: &Everything) {
                 false,
             );
         }
+        LocaValue::Markup(markup) => {
+            // The list of builtin markup keys is not trusted yet, so an unknown name may be one of
+            // them rather than a missing textformat.
+            for format in markup_formats(&markup) {
+                data.mark_used(Item::TextFormat, format.as_str());
+                if !data.item_exists(Item::TextFormat, format.as_str()) {
+                    let itype = Item::TextFormat;
+                    let msg = format!("{itype} {format} not defined in {}", itype.path());
+                    report(ErrorKey::MissingItem, itype.severity())
+                        .weak()
+                        .msg(msg)
+                        .loc(format)
+                        .push();
+                }
+            }
+        }
+        LocaValue::Tooltip(token) => data.verify_exists(Item::Localization, &token),
+        LocaValue::Icon(token) => data.verify_exists(Item::TextIcon, &token),
         _ => (),
     }
 }
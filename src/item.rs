@@ -85,6 +85,7 @@ pub enum Item {
     Localization,
     MapEnvironment,
     MapMode,
+    MapObjectData,
     Modifier,
     NamedColor,
     OnAction,
@@ -103,6 +104,7 @@ pub enum Item {
     ScriptedRule,
     ScriptedTrigger,
     ScriptValue,
+    ShaderEffect,
     Shortcut,
     Sound,
     Terrain,
@@ -489,6 +491,7 @@ impl Item {
             Item::Localization => "localization/",
             Item::MapEnvironment => "gfx/map/environment/",
             Item::MapMode => "gfx/map/map_modes/",
+            Item::MapObjectData => "gfx/map/map_object_data/",
             Item::Modifier => "common/modifiers/",
             Item::NamedColor => "common/named_colors/",
             Item::OnAction => match Game::game() {
@@ -528,6 +531,7 @@ impl Item {
             Item::ScriptedRule => "common/scripted_rules/",
             Item::ScriptedTrigger => "common/scripted_triggers/",
             Item::ScriptValue => "common/script_values/",
+            Item::ShaderEffect => "gfx/FX/",
             Item::Shortcut => "gui/shortcuts.shortcuts",
             Item::Sound => "",
             Item::Terrain => match Game::game() {
@@ -1172,10 +1176,12 @@ impl Item {
             | Item::GameConcept
             | Item::Localization
             | Item::MapEnvironment
+            | Item::MapObjectData
             | Item::NamedColor
             | Item::PortraitAnimation
             | Item::PortraitCamera
             | Item::PortraitEnvironment
+            | Item::ShaderEffect
             | Item::Sound
             | Item::TextFormat
             | Item::TextIcon
@@ -31,6 +31,7 @@ compile_error!("features \"ck3\", \"vic3\", and \"imperator\" cannot be enabled
 #[cfg(all(not(feature = "ck3"), not(feature = "vic3"), not(feature = "imperator")))]
 compile_error!("exactly one of the features \"ck3\", \"vic3\", \"imperator\" must be enabled");
 
+pub use crate::date::Date;
 pub use crate::everything::Everything;
 pub use crate::fileset::FileKind;
 pub use crate::game::Game;
@@ -38,11 +39,13 @@ pub use crate::gamedir::{find_game_directory_steam, find_paradox_directory};
 pub use crate::item::Item;
 pub use crate::modfile::ModFile;
 pub use crate::report::{
-    add_loaded_mod_root, disable_ansi_colors, emit_reports, log, set_output_file, set_output_style,
-    set_show_loaded_mods, set_show_vanilla, take_reports, Confidence, LogReport, PointedMessage,
-    Severity,
+    add_loaded_mod_root, collect_abbreviated_reports, disable_ansi_colors, emit_reports,
+    emit_reports_html, log, set_output_file, set_output_style, set_show_loaded_mods,
+    set_show_vanilla, take_reports, Confidence, LogReport, PointedMessage, ReportCounts, Severity,
 };
+pub use crate::summary::Summary;
 pub use crate::token::{Loc, Token};
+pub use crate::vfs::{is_file, open_mod_archive, path_exists};
 
 #[cfg(feature = "ck3")]
 mod ck3;
@@ -52,6 +55,8 @@ mod imperator;
 mod vic3;
 
 mod block;
+mod call_graph;
+mod callsites;
 mod config_load;
 mod context;
 mod data;
@@ -64,6 +69,7 @@ mod effect;
 mod effect_validation;
 mod everything;
 mod fileset;
+mod fontfiles;
 mod game;
 mod gamedir;
 mod gui;
@@ -81,10 +87,14 @@ mod report;
 mod rivers;
 mod scopes;
 mod script_value;
+mod shaders;
 mod stringtable;
+mod summary;
 mod token;
 mod tooltipped;
 mod trigger;
+mod trigger_logic;
 mod util;
 mod validate;
 mod validator;
+mod vfs;
@@ -1,4 +1,3 @@
-use std::fs::read;
 use std::iter::Peekable;
 use std::path::Path;
 use std::str::Chars;
@@ -9,6 +8,7 @@ use encoding_rs::WINDOWS_1252;
 use crate::fileset::FileEntry;
 use crate::report::ErrorLoc;
 use crate::token::{Loc, Token};
+use crate::vfs::read;
 
 #[derive(Clone, Debug)]
 struct CsvParser<'a> {
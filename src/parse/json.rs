@@ -1,7 +1,6 @@
 /// Parse a JSON file into a `Block`.
 /// `Block` is used, instead of a JSON-specific representation, for compatibility with the rest of the code.
 /// Unfortunately can't use serde-json because we need the locations for error reporting.
-use std::fs::read_to_string;
 use std::mem::{swap, take};
 
 use crate::block::Eq::Single;
@@ -9,6 +8,7 @@ use crate::block::{Block, Comparator, BV};
 use crate::fileset::FileEntry;
 use crate::report::{err, error, error_info, old_warn, warn_info, ErrorKey};
 use crate::token::{Loc, Token};
+use crate::vfs::read_to_string;
 
 #[derive(Copy, Clone, Debug)]
 enum State {
@@ -270,9 +270,11 @@ impl<'a> LocaParser<'a> {
             return self.error_line(key);
         }
 
-        // Skip optional number after :
+        // Optional version number after :
+        let mut version: u32 = 0;
         while let Some(c) = self.chars.peek() {
-            if c.is_ascii_digit() {
+            if let Some(digit) = c.to_digit(10) {
+                version = version.saturating_mul(10).saturating_add(digit);
                 self.next_char();
             } else {
                 break;
@@ -355,7 +357,7 @@ impl<'a> LocaParser<'a> {
         } else {
             LocaValue::Concat(take(&mut self.value))
         };
-        Some(LocaEntry::new(key, value, Some(token)))
+        Some(LocaEntry::new(key, value, Some(token)).with_version(version))
     }
 }
 
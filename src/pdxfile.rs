@@ -2,10 +2,6 @@
 //!
 //! The main entry point is [`PdxFile`].
 
-#[cfg(feature = "ck3")]
-use std::fs::read;
-use std::fs::read_to_string;
-
 #[cfg(feature = "ck3")]
 use encoding_rs::{UTF_8, WINDOWS_1252};
 
@@ -13,6 +9,9 @@ use crate::block::Block;
 use crate::fileset::FileEntry;
 use crate::parse::pdxfile::parse_pdx;
 use crate::report::{err, warn, ErrorKey};
+#[cfg(feature = "ck3")]
+use crate::vfs::read;
+use crate::vfs::read_to_string;
 
 #[cfg(feature = "ck3")]
 const BOM_AS_BYTES: &[u8] = b"\xef\xbb\xbf";
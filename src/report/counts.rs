@@ -0,0 +1,40 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::report::LogReport;
+
+/// Counts of the reports that have been emitted, for the run summary.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ReportCounts {
+    pub total: usize,
+    pub severity: BTreeMap<String, usize>,
+    pub confidence: BTreeMap<String, usize>,
+    pub key: BTreeMap<String, usize>,
+    /// Counts by the file of the report's primary location.
+    /// Only the top files make it into the summary, so this is not saved.
+    #[serde(skip)]
+    pub file: BTreeMap<String, usize>,
+}
+
+impl ReportCounts {
+    pub(crate) fn record(&mut self, report: &LogReport) {
+        let severity: &str = report.severity.into();
+        let confidence: &str = report.confidence.into();
+        self.total += 1;
+        *self.severity.entry(severity.to_string()).or_default() += 1;
+        *self.confidence.entry(confidence.to_string()).or_default() += 1;
+        *self.key.entry(report.key.to_string()).or_default() += 1;
+        let file = report.primary().loc.pathname().display().to_string();
+        *self.file.entry(file).or_default() += 1;
+    }
+
+    /// Return the `n` files with the most reports, with their report counts.
+    pub fn top_files(&self, n: usize) -> Vec<(String, usize)> {
+        let mut files: Vec<_> =
+            self.file.iter().map(|(file, &count)| (file.clone(), count)).collect();
+        files.sort_by(|(a_file, a), (b_file, b)| b.cmp(a).then(a_file.cmp(b_file)));
+        files.truncate(n);
+        files
+    }
+}
@@ -15,8 +15,10 @@ pub enum ErrorKey {
     Validation,
     Structure,
     Filename,
+    Portability,
     Encoding,
     Localization,
+    Translation,
     Markup,
     DuplicateItem,
     ExactDuplicateItem,
@@ -27,6 +29,7 @@ pub enum ErrorKey {
     MissingLocalization,
     MissingFile,
     MissingSound,
+    MissingGlyph,
     MissingItem,
     MissingPerspective,
     WrongGender,
@@ -54,6 +57,9 @@ pub enum ErrorKey {
     Colors,
     UnusedLocalization,
     UnusedFile,
+    UnusedScriptedItem,
+    UnusedGui,
+    UnusedAsset,
     UnknownList,
     Choice,
     UseOfThis,
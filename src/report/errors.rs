@@ -2,7 +2,7 @@
 
 use std::cell::RefCell;
 use std::cmp::Ordering;
-use std::fs::{read, File};
+use std::fs::File;
 use std::io::{stdout, Write};
 use std::mem::take;
 use std::path::{Path, PathBuf};
@@ -17,11 +17,14 @@ use crate::macros::MACRO_MAP;
 use crate::report::error_loc::ErrorLoc;
 use crate::report::filter::ReportFilter;
 use crate::report::writer::log_report;
+use crate::report::writer_html::log_reports_html;
 use crate::report::writer_json::log_report_json;
 use crate::report::{
-    err, tips, warn, ErrorKey, FilterRule, LogReport, OutputStyle, PointedMessage,
+    err, tips, warn, Confidence, ErrorKey, FilterRule, LogReport, OutputStyle, PointedMessage,
+    ReportCounts, ReportOverride, Severity,
 };
 use crate::token::Loc;
+use crate::vfs::read;
 
 static ERRORS: Lazy<Mutex<Errors>> = Lazy::new(|| Mutex::new(Errors::default()));
 
@@ -38,6 +41,8 @@ pub struct Errors {
 
     /// Determines whether a report should be printed.
     pub(crate) filter: ReportFilter,
+    /// Changes to the severity and confidence of reports, applied in order before filtering.
+    pub(crate) overrides: Vec<ReportOverride>,
     /// Output color and style configuration.
     pub(crate) styles: OutputStyle,
 
@@ -45,6 +50,13 @@ pub struct Errors {
     /// The "abbreviated" reports don't participate in this. They are still emitted immediately.
     /// It's a `HashSet` because duplicate reports are fairly common due to macro expansion and other revalidations.
     storage: FnvHashSet<LogReport>,
+
+    /// Counts of the reports emitted so far, for the run summary.
+    pub(crate) emitted: ReportCounts,
+
+    /// The output lines of the abbreviated reports and their headers, if they are being held
+    /// back to be included in the HTML page. Otherwise they are printed immediately.
+    pub(crate) abbreviated: Option<Vec<String>>,
 }
 
 impl Default for Errors {
@@ -54,8 +66,11 @@ impl Default for Errors {
             loaded_mods_labels: Vec::default(),
             filecache: FnvHashMap::default(),
             filter: ReportFilter::default(),
+            overrides: Vec::default(),
             styles: OutputStyle::default(),
             storage: FnvHashSet::default(),
+            emitted: ReportCounts::default(),
+            abbreviated: None,
         }
     }
 }
@@ -84,7 +99,10 @@ impl Errors {
 
     /// Perform some checks to see whether the report should actually be logged.
     /// If yes, it will add it to the storage.
-    fn push_report(&mut self, report: LogReport) {
+    fn push_report(&mut self, mut report: LogReport) {
+        for report_override in &self.overrides {
+            report_override.apply(&mut report);
+        }
         if !self.filter.should_print_report(&report) {
             return;
         }
@@ -98,20 +116,47 @@ impl Errors {
     // TODO: integrate this function into the error reporting framework.
     pub fn push_abbreviated<E: ErrorLoc>(&mut self, eloc: E, key: ErrorKey) {
         let loc = eloc.into_loc();
-        if self.filter.should_maybe_print(key, loc) {
+        if self.should_print_abbreviated(key, loc) {
             if loc.line == 0 {
-                _ = writeln!(self.output.get_mut(), "({key}) {}", loc.pathname().to_string_lossy());
+                self.write_abbreviated(format!("({key}) {}", loc.pathname().to_string_lossy()));
             } else if let Some(line) = self.get_line(loc) {
-                _ = writeln!(self.output.get_mut(), "({key}) {line}");
+                self.write_abbreviated(format!("({key}) {line}"));
             }
         }
     }
 
+    /// Print a line of abbreviated output, or hold it back for the HTML page.
+    fn write_abbreviated(&mut self, line: String) {
+        if let Some(lines) = &mut self.abbreviated {
+            lines.push(line);
+        } else {
+            _ = writeln!(self.output.get_mut(), "{line}");
+        }
+    }
+
+    /// Check whether an abbreviated report would be printed. It is treated as a warning without
+    /// a message, so that the severity and confidence overrides and the filter apply to it the
+    /// same way as to other reports.
+    fn should_print_abbreviated(&self, key: ErrorKey, loc: Loc) -> bool {
+        let mut report = LogReport {
+            severity: Severity::Warning,
+            confidence: Confidence::Reasonable,
+            key,
+            msg: String::new(),
+            info: None,
+            pointers: vec![PointedMessage::new(loc)],
+        };
+        for report_override in &self.overrides {
+            report_override.apply(&mut report);
+        }
+        self.filter.should_print_report(&report)
+    }
+
     /// Immediately print an error message. It is intended to introduce a following block of
     /// messages printed with [`Errors::push_abbreviated`].
     // TODO: integrate this function into the error reporting framework.
     pub fn push_header(&mut self, _key: ErrorKey, msg: &str) {
-        _ = writeln!(self.output.get_mut(), "{msg}");
+        self.write_abbreviated(msg.to_string());
     }
 
     /// Extract the stored reports, sort them, and return them as a vector of [`LogReport`].
@@ -158,6 +203,9 @@ impl Errors {
     /// readability and occasionally gets changed to improve that.
     pub fn emit_reports(&mut self, json: bool) {
         let reports = self.take_reports();
+        for report in &reports {
+            self.emitted.record(report);
+        }
         if json {
             _ = writeln!(self.output.get_mut(), "[");
             let mut first = true;
@@ -176,6 +224,15 @@ impl Errors {
         }
     }
 
+    /// Print all the stored reports to the error output as one self-contained HTML page.
+    pub fn emit_reports_html(&mut self) {
+        let reports = self.take_reports();
+        for report in &reports {
+            self.emitted.record(report);
+        }
+        log_reports_html(self, &reports);
+    }
+
     /// Get a mutable lock on the global ERRORS struct.
     ///
     /// # Panics
@@ -244,6 +301,11 @@ pub fn will_maybe_log<E: ErrorLoc>(eloc: E, key: ErrorKey) -> bool {
     Errors::get().filter.should_maybe_print(key, eloc.into_loc())
 }
 
+/// Tests whether an abbreviated report, as logged by [`warn_abbreviated`], will be printed.
+pub(crate) fn will_log_abbreviated<E: ErrorLoc>(eloc: E, key: ErrorKey) -> bool {
+    Errors::get().should_print_abbreviated(key, eloc.into_loc())
+}
+
 /// Print all the stored reports to the error output.
 /// Set `json` if they should be printed as a JSON array. Otherwise they are printed in the
 /// default output format.
@@ -254,6 +316,23 @@ pub fn emit_reports(json: bool) {
     Errors::get_mut().emit_reports(json);
 }
 
+/// Print all the stored reports to the error output as one self-contained HTML page, which can
+/// group and filter them in the browser.
+pub fn emit_reports_html() {
+    Errors::get_mut().emit_reports_html();
+}
+
+/// Hold back the abbreviated reports, such as the lists of unused items, instead of printing
+/// them immediately. [`emit_reports_html`] will then include them in the page.
+pub fn collect_abbreviated_reports() {
+    Errors::get_mut().abbreviated.get_or_insert_with(Vec::new);
+}
+
+/// Return the counts of the reports emitted so far by [`emit_reports`] and [`emit_reports_html`].
+pub fn emitted_report_counts() -> ReportCounts {
+    Errors::get().emitted.clone()
+}
+
 /// Extract the stored reports, sort them, and return them as a vector of [`LogReport`].
 /// The stored reports will be left empty.
 pub fn take_reports() -> Vec<LogReport> {
@@ -361,3 +440,9 @@ pub fn set_show_loaded_mods(v: bool) {
 pub(crate) fn set_predicate(predicate: FilterRule) {
     Errors::get_mut().filter.predicate = predicate;
 }
+
+/// Configure the error reporter to change the severity and confidence of reports according to
+/// these overrides. Later overrides take precedence over earlier ones.
+pub(crate) fn set_overrides(overrides: Vec<ReportOverride>) {
+    Errors::get_mut().overrides = overrides;
+}
@@ -1,19 +1,24 @@
 //! Error report collection and printing facilities.
 
 pub(crate) use builder::{err, fatal, report, tips, untidy, warn};
+pub use counts::ReportCounts;
 pub(crate) use error_key::ErrorKey;
 pub(crate) use error_loc::ErrorLoc;
 pub use errors::*;
 pub(crate) use filter::FilterRule;
 pub(crate) use output_style::OutputStyle;
+pub(crate) use overrides::ReportOverride;
 pub use report_struct::{Confidence, LogReport, PointedMessage, Severity};
 
 mod builder;
+mod counts;
 mod error_key;
 mod error_loc;
 mod errors;
 mod filter;
 mod output_style;
+mod overrides;
 mod report_struct;
 mod writer;
+mod writer_html;
 mod writer_json;
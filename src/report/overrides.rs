@@ -0,0 +1,88 @@
+use crate::report::{Confidence, ErrorKey, LogReport, Severity};
+
+/// Changes the severity or confidence of reports with a given `ErrorKey`.
+/// Configured by the `severity_override` and `confidence_override` blocks in the config file.
+///
+/// Unlike a [`FilterRule`](crate::report::FilterRule), an override does not hide any reports.
+#[derive(Debug)]
+pub struct ReportOverride {
+    pub key: ErrorKey,
+    /// If not empty, the override only applies to reports whose primary location is in a file
+    /// matching one of these patterns.
+    pub files: Vec<String>,
+    /// If set, the override only applies to reports whose msg contains this text.
+    pub text: Option<String>,
+    pub severity: Option<Severity>,
+    pub confidence: Option<Confidence>,
+}
+
+impl ReportOverride {
+    pub fn new(key: ErrorKey) -> Self {
+        Self { key, files: Vec::new(), text: None, severity: None, confidence: None }
+    }
+
+    fn matches(&self, report: &LogReport) -> bool {
+        if report.key != self.key {
+            return false;
+        }
+        if let Some(text) = &self.text {
+            if !report.msg.to_lowercase().contains(&text.to_lowercase()) {
+                return false;
+            }
+        }
+        if self.files.is_empty() {
+            return true;
+        }
+        let Some(pointer) = report.pointers.first() else {
+            return false;
+        };
+        let path = pointer.loc.pathname().to_string_lossy().replace('\\', "/");
+        self.files.iter().any(|pattern| path_matches(pattern, &path))
+    }
+
+    /// Change the report's severity and confidence if this override applies to it.
+    pub fn apply(&self, report: &mut LogReport) {
+        if self.matches(report) {
+            if let Some(severity) = self.severity {
+                report.severity = severity;
+            }
+            if let Some(confidence) = self.confidence {
+                report.confidence = confidence;
+            }
+        }
+    }
+}
+
+/// A pattern without wildcards matches like the `file` filter trigger, by path prefix.
+/// Otherwise `*` matches within one path component, `**` matches across components,
+/// and `?` matches one character.
+fn path_matches(pattern: &str, path: &str) -> bool {
+    if pattern.contains(['*', '?']) {
+        glob_matches(pattern.as_bytes(), path.as_bytes())
+    } else {
+        std::path::Path::new(path).starts_with(pattern)
+    }
+}
+
+fn glob_matches(pattern: &[u8], path: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((b'*', rest)) if rest.first() == Some(&b'*') => {
+            let rest = &rest[1..];
+            // `a/**/b` also matches `a/b`
+            if let Some(after) = rest.strip_prefix(b"/") {
+                if glob_matches(after, path) {
+                    return true;
+                }
+            }
+            (0..=path.len()).any(|i| glob_matches(rest, &path[i..]))
+        }
+        Some((b'*', rest)) => (0..=path.len())
+            .take_while(|&i| i == 0 || path[i - 1] != b'/')
+            .any(|i| glob_matches(rest, &path[i..])),
+        Some((b'?', rest)) => {
+            path.first().is_some_and(|&c| c != b'/') && glob_matches(rest, &path[1..])
+        }
+        Some((c, rest)) => path.first() == Some(c) && glob_matches(rest, &path[1..]),
+    }
+}
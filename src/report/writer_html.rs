@@ -0,0 +1,263 @@
+use std::fmt::Write as _;
+
+use strum::IntoEnumIterator;
+
+use crate::game::GameFlags;
+use crate::report::errors::Errors;
+use crate::report::writer::kind_tag;
+use crate::report::{Confidence, LogReport, PointedMessage, Severity};
+
+const STYLE: &str = r"
+body { font-family: sans-serif; margin: 0; background: #fafafa; color: #222; }
+header { position: sticky; top: 0; background: #fff; border-bottom: 1px solid #ccc; padding: 0.5em 1em; z-index: 1; }
+header h1 { font-size: 1.2em; margin: 0 0 0.4em 0; }
+.controls { display: flex; flex-wrap: wrap; gap: 0.5em 1.5em; align-items: center; }
+.controls input[type=search] { min-width: 25em; }
+main { padding: 0.5em 1em; }
+details.group { margin: 0.3em 0; border: 1px solid #ddd; background: #fff; }
+details.group > summary { cursor: pointer; padding: 0.3em 0.5em; font-weight: bold; }
+details.group > summary .count { font-weight: normal; color: #666; }
+.report { border-top: 1px solid #eee; padding: 0.4em 0.8em; }
+.title .severity { font-weight: bold; }
+.title .msg { font-weight: bold; }
+.loc { color: #3465a4; font-family: monospace; margin-top: 0.3em; }
+pre.source { margin: 0.1em 0 0.1em 1em; tab-size: 4; white-space: pre-wrap; }
+pre.source .linenr { color: #3465a4; user-select: none; }
+pre.source mark { padding: 0 1px; }
+.tag { font-family: monospace; margin-left: 1em; }
+.info { margin-top: 0.3em; color: #4e7a27; }
+section.abbreviated { padding: 0.5em 1em; }
+section.abbreviated h2 { font-size: 1.1em; }
+.sev-fatal .severity, .sev-fatal mark { color: #fff; background: #a40000; }
+.sev-error .severity, .sev-fatal .tag, .sev-error .tag { color: #cc0000; }
+.sev-error mark { background: #f8c8c8; }
+.sev-warning .severity, .sev-warning .tag { color: #a06000; }
+.sev-warning mark { background: #fce29a; }
+.sev-untidy .severity, .sev-untidy .tag { color: #06989a; }
+.sev-untidy mark { background: #c4ecec; }
+.sev-tips .severity, .sev-tips .tag { color: #4e9a06; }
+.sev-tips mark { background: #d5f0c0; }
+";
+
+const SCRIPT: &str = r#"
+const SEVERITY_ORDER = { fatal: 0, error: 1, warning: 2, untidy: 3, tips: 4 };
+const CONFIDENCE_ORDER = { weak: 0, reasonable: 1, strong: 2 };
+const reports = Array.from(document.querySelectorAll(".report"));
+const groups = document.getElementById("groups");
+
+function regroup() {
+    const by = document.getElementById("group").value;
+    const map = new Map();
+    for (const report of reports) {
+        const name = report.dataset[by];
+        if (!map.has(name)) {
+            map.set(name, []);
+        }
+        map.get(name).push(report);
+    }
+    const names = Array.from(map.keys());
+    if (by === "severity") {
+        names.sort((a, b) => SEVERITY_ORDER[a] - SEVERITY_ORDER[b]);
+    } else {
+        names.sort((a, b) => a.localeCompare(b));
+    }
+    groups.replaceChildren();
+    for (const name of names) {
+        const details = document.createElement("details");
+        details.className = "group";
+        details.open = true;
+        const summary = document.createElement("summary");
+        const label = document.createElement("span");
+        label.textContent = name;
+        const count = document.createElement("span");
+        count.className = "count";
+        summary.append(label, " ", count);
+        details.append(summary, ...map.get(name));
+        groups.append(details);
+    }
+    applyFilter();
+}
+
+function applyFilter() {
+    const words = document.getElementById("search").value.toLowerCase().split(/\s+/).filter((w) => w);
+    const severities = new Set(
+        Array.from(document.querySelectorAll(".severity-toggle:checked")).map((c) => c.value)
+    );
+    const confidence = CONFIDENCE_ORDER[document.getElementById("confidence").value];
+    let shown = 0;
+    for (const report of reports) {
+        report.hidden = !(
+            severities.has(report.dataset.severity) &&
+            CONFIDENCE_ORDER[report.dataset.confidence] >= confidence &&
+            words.every((w) => report.dataset.search.includes(w))
+        );
+        if (!report.hidden) {
+            shown += 1;
+        }
+    }
+    for (const details of groups.children) {
+        const count = details.querySelectorAll(".report:not([hidden])").length;
+        details.hidden = count === 0;
+        details.querySelector(".count").textContent = `(${count})`;
+    }
+    document.getElementById("shown").textContent = shown;
+}
+
+function setOpen(open) {
+    for (const details of groups.children) {
+        details.open = open;
+    }
+}
+
+document.getElementById("group").addEventListener("change", regroup);
+document.getElementById("search").addEventListener("input", applyFilter);
+document.getElementById("confidence").addEventListener("change", applyFilter);
+for (const toggle of document.querySelectorAll(".severity-toggle")) {
+    toggle.addEventListener("change", applyFilter);
+}
+document.getElementById("expand").addEventListener("click", () => setOpen(true));
+document.getElementById("collapse").addEventListener("click", () => setOpen(false));
+regroup();
+"#;
+
+/// Log all the reports as one self-contained HTML page, with controls for grouping and filtering
+/// them in the browser.
+pub fn log_reports_html(errors: &mut Errors, reports: &[LogReport]) {
+    let mut html = String::new();
+    let title = format!("Tiger report for {}", GameFlags::game());
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    _ = writeln!(html, "<title>{}</title>", escape(&title));
+    _ = writeln!(html, "<style>{STYLE}</style>\n</head>\n<body>");
+
+    _ = writeln!(html, "<header>\n<h1>{}</h1>\n<div class=\"controls\">", escape(&title));
+    html.push_str(
+        "<input type=\"search\" id=\"search\" placeholder=\"Search messages, keys and files\">\n",
+    );
+    html.push_str("<label>Group by <select id=\"group\"><option value=\"file\">file</option><option value=\"key\">key</option><option value=\"severity\">severity</option></select></label>\n");
+    html.push_str("<span>");
+    for severity in Severity::iter().rev() {
+        let count = reports.iter().filter(|report| report.severity == severity).count();
+        let name: &str = severity.into();
+        _ = write!(
+            html,
+            "<label class=\"sev-{name}\"><input type=\"checkbox\" class=\"severity-toggle\" value=\"{name}\" checked><span class=\"severity\">{severity}</span> ({count})</label> "
+        );
+    }
+    html.push_str("</span>\n<label>Confidence at least <select id=\"confidence\">");
+    for confidence in Confidence::iter() {
+        let name: &str = confidence.into();
+        _ = write!(html, "<option value=\"{name}\">{name}</option>");
+    }
+    html.push_str("</select></label>\n");
+    html.push_str("<span><button id=\"expand\">Expand all</button> <button id=\"collapse\">Collapse all</button></span>\n");
+    _ = writeln!(
+        html,
+        "<span>Showing <span id=\"shown\">{0}</span> of {0} reports</span>",
+        reports.len()
+    );
+    html.push_str("</div>\n</header>\n<main id=\"groups\">\n");
+
+    for report in reports {
+        html_report(errors, &mut html, report);
+    }
+    html.push_str("</main>\n");
+
+    if let Some(lines) = errors.abbreviated.take() {
+        html.push_str("<section class=\"abbreviated\">\n<h2>Unused items</h2>\n<pre>");
+        for line in lines {
+            _ = writeln!(html, "{}", escape(&line));
+        }
+        html.push_str("</pre>\n</section>\n");
+    }
+
+    _ = writeln!(html, "<script>{SCRIPT}</script>\n</body>\n</html>");
+    _ = errors.output.get_mut().write_all(html.as_bytes());
+}
+
+fn html_report(errors: &mut Errors, html: &mut String, report: &LogReport) {
+    let severity: &str = report.severity.into();
+    let confidence: &str = report.confidence.into();
+    let file = report.primary().loc.pathname().display().to_string();
+
+    let mut search =
+        format!("{} {} {}", report.key, report.msg, report.info.as_deref().unwrap_or(""));
+    for pointer in &report.pointers {
+        _ = write!(search, " {}", pointer.loc.pathname().display());
+    }
+
+    _ = writeln!(
+        html,
+        "<div class=\"report sev-{severity}\" data-severity=\"{severity}\" data-confidence=\"{confidence}\" data-key=\"{}\" data-file=\"{}\" data-search=\"{}\">",
+        report.key,
+        escape(&file),
+        escape(&search.to_lowercase()),
+    );
+    _ = writeln!(
+        html,
+        "<div class=\"title\"><span class=\"severity\">{}</span><span class=\"key\">({})</span>: <span class=\"msg\">{}</span></div>",
+        report.severity,
+        report.key,
+        escape(&report.msg)
+    );
+
+    let mut previous: Option<&PointedMessage> = None;
+    for pointer in &report.pointers {
+        if !previous.is_some_and(|previous| previous.loc.same_file(pointer.loc)) {
+            _ = writeln!(
+                html,
+                "<div class=\"loc\">--&gt; [{}] {}</div>",
+                escape(kind_tag(errors, pointer.loc.kind)),
+                escape(&pointer.loc.pathname().display().to_string())
+            );
+        }
+        previous = Some(pointer);
+        if pointer.loc.line == 0 {
+            continue;
+        }
+        if let Some(line) = errors.get_line(pointer.loc) {
+            // The column is counted in characters, starting at 1
+            let start = (pointer.loc.column as usize).saturating_sub(1);
+            let end = start + pointer.length.max(1);
+            let mut source = String::new();
+            for (i, c) in line.chars().enumerate() {
+                if i == start {
+                    source.push_str("<mark>");
+                }
+                source.push_str(&escape(&c.to_string()));
+                if i + 1 == end {
+                    source.push_str("</mark>");
+                }
+            }
+            if start < line.chars().count() && end > line.chars().count() {
+                source.push_str("</mark>");
+            }
+            _ = writeln!(
+                html,
+                "<pre class=\"source\"><span class=\"linenr\">{} | </span>{source}</pre>",
+                pointer.loc.line
+            );
+        }
+        if let Some(msg) = &pointer.msg {
+            _ = writeln!(html, "<div class=\"tag\">&lt;-- {}</div>", escape(msg));
+        }
+    }
+    if let Some(info) = &report.info {
+        _ = writeln!(html, "<div class=\"info\">Info: {}</div>", escape(info));
+    }
+    html.push_str("</div>\n");
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
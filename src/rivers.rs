@@ -2,7 +2,7 @@
 //!
 //! The `rivers.png` file has detailed requirements for its image format and the layout of every pixel.
 
-use std::fs::File;
+use std::io::Cursor;
 use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Result};
@@ -12,6 +12,7 @@ use png::{ColorType, Decoder};
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
 use crate::report::{err, warn, will_maybe_log, ErrorKey};
+use crate::vfs::read;
 
 #[derive(Clone, Debug, Default)]
 pub struct Rivers {
@@ -26,7 +27,7 @@ pub struct Rivers {
 
 impl Rivers {
     pub fn load_png(&mut self, fullpath: &Path) -> Result<()> {
-        let decoder = Decoder::new(File::open(fullpath)?);
+        let decoder = Decoder::new(Cursor::new(read(fullpath)?));
         let mut reader = decoder.read_info()?;
 
         let info = reader.info();
@@ -10,7 +10,7 @@ use crate::everything::Everything;
 use crate::helpers::TriBool;
 use crate::item::Item;
 use crate::lowercase::Lowercase;
-use crate::report::{advice_info, err, error, error_info, old_warn, untidy, ErrorKey};
+use crate::report::{advice_info, err, error, error_info, old_warn, untidy, warn, ErrorKey};
 use crate::scopes::{scope_iterator, Scopes};
 use crate::token::Token;
 use crate::tooltipped::Tooltipped;
@@ -47,7 +47,7 @@ fn validate_inner(
 
     let mut made_changes = false;
 
-    validate_ifelse_sequence(block, "if", "else_if", "else");
+    validate_ifelse_sequence(block, data, "if", "else_if", "else");
     vd.set_allow_questionmark_equals(true);
     vd.unknown_fields_cmp(|token, cmp, bv| {
         if token.is("save_temporary_scope_as") {
@@ -80,6 +80,13 @@ fn validate_inner(
                 old_warn(token, ErrorKey::Logic, &msg);
             }
             validate_bv(bv, data, sc, check_desc);
+            if !token.is("multiply")
+                && eval_script_value(bv, data).and_then(ValueRange::exact_value) == Some(0.0)
+            {
+                let msg = format!("{token} by zero");
+                let info = "the divisor here is always 0";
+                err(ErrorKey::Range).msg(msg).info(info).loc(token).push();
+            }
             made_changes = true;
         } else if token.is("round") || token.is("ceiling") || token.is("floor") || token.is("abs") {
             if have_value == TriBool::False {
@@ -155,6 +162,19 @@ fn validate_inner(
             sc.close();
         }
     });
+
+    // `min` and `max` in a calculation are bounds on the value, so they should not cross.
+    if let (Some(min_key), Some(max_key)) = (block.get_key("min"), block.get_key("max")) {
+        let min = block.get_field("min").and_then(|bv| eval_script_value(bv, data));
+        let max = block.get_field("max").and_then(|bv| eval_script_value(bv, data));
+        if let (Some(min), Some(max)) = (min, max) {
+            if min.min > max.max {
+                let msg = "`min` is larger than `max`";
+                let info = "the value will always be whichever bound is applied last";
+                warn(ErrorKey::Range).msg(msg).info(info).loc(max_key).loc(min_key, "min").push();
+            }
+        }
+    }
     made_changes
 }
 
@@ -212,6 +232,17 @@ fn validate_minmax_range(
     vd.multi_field_validated("max", |bv, data| {
         validate_bv(bv, data, sc, check_desc);
     });
+
+    if let (Some(min), Some(max)) = (block.get_field("min"), block.get_field("max")) {
+        if let (Some(min_range), Some(max_range)) =
+            (eval_script_value(min, data), eval_script_value(max, data))
+        {
+            if min_range.min > max_range.max {
+                let msg = "`min` is always larger than `max` in this range";
+                warn(ErrorKey::Range).msg(msg).loc(max).loc(min, "min").push();
+            }
+        }
+    }
 }
 
 /// Validate `if` or `else_if` blocks that are part of a script value.
@@ -280,9 +311,21 @@ fn validate_bv(bv: &BV, data: &Everything, sc: &mut ScopeContext, check_desc: bo
                 // It's a range like { 1 5 }
                 let vec = vd.values();
                 if vec.len() == 2 {
-                    for v in vec {
+                    for v in &vec {
                         validate_target_ok_this(v, data, sc, Scopes::Value | Scopes::Bool);
                     }
+                    let low = eval_token(vec[0], data, 0);
+                    let high = eval_token(vec[1], data, 0);
+                    if let (Some(low), Some(high)) = (low, high) {
+                        if low.min > high.max {
+                            let msg = "the lower end of this range is larger than the upper end";
+                            warn(ErrorKey::Range)
+                                .msg(msg)
+                                .loc(vec[1])
+                                .loc(vec[0], "lower end")
+                                .push();
+                        }
+                    }
                 } else {
                     old_warn(b, ErrorKey::Validation, "invalid script value range");
                 }
@@ -318,3 +361,234 @@ pub fn validate_non_dynamic_script_value(bv: &BV, data: &Everything) {
     let info = "only literal numbers or the name of a simple script value";
     error_info(bv, ErrorKey::Validation, msg, info);
 }
+
+/// Warn about a percentage chance, such as the `chance` of a `random` effect, that can be
+/// determined in advance to always be 0 or more than 100.
+pub fn check_chance(key: &Token, bv: &BV, data: &Everything) {
+    if let Some(range) = eval_script_value(bv, data) {
+        if range.max <= 0.0 {
+            let msg = format!("`{key}` is always 0");
+            let info = "this will never happen";
+            warn(ErrorKey::Range).msg(msg).info(info).loc(key).push();
+        } else if range.min > 100.0 {
+            let msg = format!("`{key}` is always more than 100");
+            let info = "this will always happen; the percentage may be wrong";
+            warn(ErrorKey::Range).msg(msg).info(info).loc(key).push();
+        }
+    }
+}
+
+/// The range of values that a script value can take, as far as can be determined without knowing
+/// the game state.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ValueRange {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl ValueRange {
+    pub fn exact(value: f64) -> Self {
+        Self { min: value, max: value }
+    }
+
+    /// Return the value if it is always the same.
+    pub fn exact_value(self) -> Option<f64> {
+        #[allow(clippy::float_cmp)] // exact comparison is intended here
+        (self.min == self.max).then_some(self.min)
+    }
+
+    fn union(self, other: Self) -> Self {
+        Self { min: self.min.min(other.min), max: self.max.max(other.max) }
+    }
+
+    fn from_corners(corners: [f64; 4]) -> Self {
+        let min = corners.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = corners.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        Self { min, max }
+    }
+
+    pub fn add(self, other: Self) -> Self {
+        Self { min: self.min + other.min, max: self.max + other.max }
+    }
+
+    fn subtract(self, other: Self) -> Self {
+        Self { min: self.min - other.max, max: self.max - other.min }
+    }
+
+    pub fn multiply(self, other: Self) -> Self {
+        Self::from_corners([
+            self.min * other.min,
+            self.min * other.max,
+            self.max * other.min,
+            self.max * other.max,
+        ])
+    }
+
+    /// Returns `None` if the divisor might be zero.
+    fn divide(self, other: Self) -> Option<Self> {
+        if other.min <= 0.0 && other.max >= 0.0 {
+            return None;
+        }
+        Some(Self::from_corners([
+            self.min / other.min,
+            self.min / other.max,
+            self.max / other.min,
+            self.max / other.max,
+        ]))
+    }
+
+    fn abs(self) -> Self {
+        if self.min >= 0.0 {
+            self
+        } else if self.max <= 0.0 {
+            Self { min: -self.max, max: -self.min }
+        } else {
+            Self { min: 0.0, max: (-self.min).max(self.max) }
+        }
+    }
+
+    fn map(self, f: fn(f64) -> f64) -> Self {
+        Self { min: f(self.min), max: f(self.max) }
+    }
+}
+
+/// How deeply to follow references to defines and other script values before giving up.
+/// This also protects against script values that refer to themselves.
+const MAX_EVAL_DEPTH: usize = 16;
+
+/// Evaluate a script value that depends only on literals, defines, and other script values that
+/// are themselves constant. `@`-values have already been substituted by the parser.
+///
+/// Returns the range of values it can take, which is only a single value if there are no `if`
+/// branches or random ranges involved. Returns `None` if the script value depends on the game state.
+pub fn eval_script_value(bv: &BV, data: &Everything) -> Option<ValueRange> {
+    eval_bv(bv, data, 0)
+}
+
+fn eval_bv(bv: &BV, data: &Everything, depth: usize) -> Option<ValueRange> {
+    if depth > MAX_EVAL_DEPTH {
+        return None;
+    }
+    match bv {
+        BV::Value(token) => eval_token(token, data, depth),
+        BV::Block(block) => {
+            if matches!(block.iter_items().next(), Some(BlockItem::Block(_) | BlockItem::Value(_)))
+            {
+                // It's a range like { 1 5 }
+                let mut values = Vec::new();
+                for item in block.iter_items() {
+                    if let BlockItem::Value(token) = item {
+                        values.push(eval_token(token, data, depth)?);
+                    } else {
+                        return None;
+                    }
+                }
+                match values[..] {
+                    [low, high] => Some(low.union(high)),
+                    _ => None,
+                }
+            } else {
+                eval_block(block, data, depth, ValueRange::exact(0.0))
+            }
+        }
+    }
+}
+
+fn eval_token(token: &Token, data: &Everything, depth: usize) -> Option<ValueRange> {
+    if let Some(value) = token.get_number() {
+        Some(ValueRange::exact(value))
+    } else if let Some(define) = token.as_str().strip_prefix("define:") {
+        eval_bv(data.defines.get_bv(define)?, data, depth + 1)
+    } else {
+        eval_bv(data.script_values.get_bv(token.as_str())?, data, depth + 1)
+    }
+}
+
+/// Evaluate a calculation block, starting from `value`.
+fn eval_block(
+    block: &Block,
+    data: &Everything,
+    depth: usize,
+    mut value: ValueRange,
+) -> Option<ValueRange> {
+    // The value before the current `if` sequence, and the union of the outcomes of its branches.
+    let mut ifelse: Option<(ValueRange, ValueRange)> = None;
+
+    for item in block.iter_items() {
+        let BlockItem::Field(field) = item else {
+            return None;
+        };
+        if !field.is_eq() {
+            return None;
+        }
+        let key = Lowercase::new(field.key().as_str());
+        let bv = field.bv();
+        match key.as_str() {
+            "desc" | "format" | "limit" | "save_temporary_scope_as" | "save_temporary_value_as" => {
+                continue;
+            }
+            "value" => value = eval_bv(bv, data, depth)?,
+            "add" => value = value.add(eval_bv(bv, data, depth)?),
+            "subtract" => value = value.subtract(eval_bv(bv, data, depth)?),
+            "multiply" => value = value.multiply(eval_bv(bv, data, depth)?),
+            "divide" => value = value.divide(eval_bv(bv, data, depth)?)?,
+            "modulo" => {
+                let divisor = eval_bv(bv, data, depth)?.exact_value()?;
+                #[allow(clippy::float_cmp)] // exact comparison is intended here
+                if divisor == 0.0 {
+                    return None;
+                }
+                value = ValueRange::exact(value.exact_value()? % divisor);
+            }
+            // `min` is a lower bound on the value and `max` is an upper bound
+            "min" => {
+                let bound = eval_bv(bv, data, depth)?;
+                value = ValueRange { min: value.min.max(bound.min), max: value.max.max(bound.max) };
+            }
+            "max" => {
+                let bound = eval_bv(bv, data, depth)?;
+                value = ValueRange { min: value.min.min(bound.min), max: value.max.min(bound.max) };
+            }
+            "round" | "ceiling" | "floor" | "abs" => {
+                if bv.get_value()?.is("yes") {
+                    value = match key.as_str() {
+                        "round" => value.map(f64::round),
+                        "ceiling" => value.map(f64::ceil),
+                        "floor" => value.map(f64::floor),
+                        _ => value.abs(),
+                    };
+                }
+            }
+            "fixed_range" | "integer_range" => {
+                let block = bv.get_block()?;
+                let min = eval_bv(block.get_field("min")?, data, depth)?;
+                let max = eval_bv(block.get_field("max")?, data, depth)?;
+                value = min.union(max);
+            }
+            "if" | "else_if" | "else" => {
+                let block = bv.get_block()?;
+                // An `else_if` or `else` without a preceding `if` is reported elsewhere.
+                let (before, branches) = match (key.as_str(), ifelse) {
+                    ("if", _) | (_, None) => {
+                        let branch = eval_block(block, data, depth, value)?;
+                        (value, branch)
+                    }
+                    (_, Some((before, branches))) => {
+                        (before, branches.union(eval_block(block, data, depth, before)?))
+                    }
+                };
+                if key.as_str() == "else" {
+                    value = branches;
+                    ifelse = None;
+                } else {
+                    value = before.union(branches);
+                    ifelse = Some((before, branches));
+                }
+                continue;
+            }
+            _ => return None,
+        }
+        ifelse = None;
+    }
+    Some(value)
+}
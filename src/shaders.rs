@@ -0,0 +1,298 @@
+//! Loader and validator for the shader files (`.shader` and `.fxh`) under `gfx/FX/`.
+//!
+//! These are not in script format, and most of their content is shader code, so only the
+//! `Includes`, the `MainCode` names of the vertex and pixel shaders, and the `Effect` definitions
+//! are extracted. The gui and the mesh settings in `.asset` files refer to the effects by name.
+//!
+//! The extraction has not been verified against all the vanilla shader files, so the reports
+//! that depend on it are weak.
+
+use std::path::PathBuf;
+
+use fnv::{FnvHashMap, FnvHashSet};
+
+use crate::everything::Everything;
+use crate::fileset::{FileEntry, FileHandler};
+use crate::item::Item;
+use crate::report::{err, warn, ErrorKey};
+use crate::token::{Loc, Token};
+use crate::vfs::read_to_string;
+
+#[derive(Clone, Debug, Default)]
+pub struct Shaders {
+    effects: FnvHashMap<String, Token>,
+    /// The parsed shader files, keyed by their path relative to `gfx/FX/`, which is also how
+    /// `Includes` refers to them.
+    files: FnvHashMap<String, ShaderFile>,
+}
+
+/// The parts of a shader file that other files can refer to.
+#[derive(Clone, Debug, Default)]
+pub struct ShaderFile {
+    includes: Vec<Token>,
+    vertex_shaders: Vec<Token>,
+    pixel_shaders: Vec<Token>,
+    effects: Vec<ShaderEffect>,
+}
+
+/// An `Effect` definition, with the names of the vertex and pixel shaders it uses.
+#[derive(Clone, Debug)]
+struct ShaderEffect {
+    name: Token,
+    vertex_shader: Option<Token>,
+    pixel_shader: Option<Token>,
+}
+
+impl Shaders {
+    pub fn effect_exists(&self, key: &str) -> bool {
+        self.effects.contains_key(key)
+    }
+
+    pub fn iter_effect_keys(&self) -> impl Iterator<Item = &Token> {
+        self.effects.values()
+    }
+
+    /// Return the files included by `path`, directly or indirectly, including `path` itself.
+    /// Returns `None` if `path` or any of its includes was not loaded.
+    fn include_closure(&self, path: &str) -> Option<Vec<&ShaderFile>> {
+        let mut seen = FnvHashSet::default();
+        let mut todo = vec![path];
+        let mut closure = Vec::new();
+        while let Some(path) = todo.pop() {
+            if seen.insert(path) {
+                let file = self.files.get(path)?;
+                todo.extend(file.includes.iter().map(Token::as_str));
+                closure.push(file);
+            }
+        }
+        Some(closure)
+    }
+
+    /// Verify that `effect` is defined in the shader file `file`, or in one of the files it includes.
+    /// `file` is the full path, starting with `gfx/FX/`.
+    pub fn verify_effect_in_file(&self, file: &Token, effect: &Token, data: &Everything) {
+        let path = file.as_str().strip_prefix("gfx/FX/").unwrap_or(file.as_str());
+        let Some(closure) = self.include_closure(path) else {
+            // A missing shader file is reported elsewhere, so just check the effect name
+            data.verify_exists(Item::ShaderEffect, effect);
+            return;
+        };
+        if !closure.iter().any(|f| f.effects.iter().any(|e| e.name.is(effect.as_str()))) {
+            let msg = format!("shader effect {effect} not defined in {file} or its includes");
+            warn(ErrorKey::MissingItem).weak().msg(msg).loc(effect).loc(file, "shader file").push();
+        }
+    }
+
+    pub fn validate(&self, _data: &Everything) {
+        for (path, file) in &self.files {
+            let mut complete = true;
+            for include in &file.includes {
+                if self.include_closure(include.as_str()).is_none() {
+                    complete = false;
+                    if !self.files.contains_key(include.as_str()) {
+                        let msg = format!("shader include {include} not found in gfx/FX/");
+                        err(ErrorKey::MissingFile).weak().msg(msg).loc(include).push();
+                    }
+                }
+            }
+            // Without all the includes, the shader names can't be checked
+            if !complete {
+                continue;
+            }
+            let closure = self.include_closure(path).unwrap_or_default();
+            for effect in &file.effects {
+                if let Some(name) = &effect.vertex_shader {
+                    if !closure.iter().any(|f| f.vertex_shaders.iter().any(|s| s.is(name.as_str())))
+                    {
+                        let msg = format!(
+                            "vertex shader {name} not defined in this file or its includes"
+                        );
+                        err(ErrorKey::MissingItem).weak().msg(msg).loc(name).push();
+                    }
+                }
+                if let Some(name) = &effect.pixel_shader {
+                    if !closure.iter().any(|f| f.pixel_shaders.iter().any(|s| s.is(name.as_str())))
+                    {
+                        let msg =
+                            format!("pixel shader {name} not defined in this file or its includes");
+                        err(ErrorKey::MissingItem).weak().msg(msg).loc(name).push();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Split a shader file into tokens, skipping comments and the `[[ ]]` sections of shader code.
+    /// Outside of those sections, both `#` and `//` start a comment.
+    /// The punctuation `{ } ( ) =` becomes separate tokens, and quoted strings lose their quotes.
+    fn tokenize(content: &str, loc: Loc) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut in_code = false;
+        let mut in_comment = false;
+        for (nr, line) in content.lines().enumerate() {
+            let mut loc = loc;
+            loc.line = u32::try_from(nr + 1).unwrap_or(u32::MAX);
+            let mut rest = line;
+            while !rest.is_empty() {
+                if in_comment {
+                    let Some(end) = rest.find("*/") else {
+                        break;
+                    };
+                    in_comment = false;
+                    rest = &rest[end + 2..];
+                } else if in_code {
+                    let Some(end) = rest.find("]]") else {
+                        break;
+                    };
+                    in_code = false;
+                    rest = &rest[end + 2..];
+                } else {
+                    // Take the script part up to the next comment or code section
+                    let end = ["#", "//", "/*", "[["]
+                        .iter()
+                        .filter_map(|marker| rest.find(marker))
+                        .min()
+                        .unwrap_or(rest.len());
+                    Self::tokenize_part(line, &rest[..end], loc, &mut tokens);
+                    let marker = &rest[end..];
+                    if marker.starts_with('#') || marker.starts_with("//") {
+                        break;
+                    }
+                    in_comment = marker.starts_with("/*");
+                    in_code = marker.starts_with("[[");
+                    rest = marker.get(2..).unwrap_or("");
+                }
+            }
+        }
+        tokens
+    }
+
+    /// Tokenize `part`, which is a slice of `line`.
+    fn tokenize_part(line: &str, part: &str, mut loc: Loc, tokens: &mut Vec<Token>) {
+        let mut push = |word: &str| {
+            // `word` is a slice of `line`, so this gives its offset in the line
+            let column = word.as_ptr() as usize - line.as_ptr() as usize;
+            loc.column = u16::try_from(column + 1).unwrap_or(u16::MAX);
+            tokens.push(Token::new(word, loc));
+        };
+        for word in part.split_whitespace() {
+            let mut rest = word;
+            while let Some(pos) = rest.find(['{', '}', '(', ')', '=']) {
+                if pos > 0 {
+                    push(rest[..pos].trim_matches('"'));
+                }
+                push(&rest[pos..=pos]);
+                rest = &rest[pos + 1..];
+            }
+            if !rest.is_empty() {
+                let unquoted = rest.strip_prefix('"').unwrap_or(rest);
+                push(unquoted.strip_suffix('"').unwrap_or(unquoted));
+            }
+        }
+    }
+
+    /// Return the index just past the `}` that closes the `{` at `tokens[start]`.
+    fn block_end(tokens: &[Token], start: usize) -> usize {
+        let mut depth = 0;
+        for (i, token) in tokens.iter().enumerate().skip(start) {
+            if token.is("{") {
+                depth += 1;
+            } else if token.is("}") {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+        }
+        tokens.len()
+    }
+
+    fn parse(tokens: &[Token]) -> ShaderFile {
+        let is = |i: usize, s: &str| tokens.get(i).is_some_and(|t| t.is(s));
+        let mut file = ShaderFile::default();
+        let mut i = 0;
+        while i < tokens.len() {
+            let token = &tokens[i];
+            if token.is("Includes") && is(i + 1, "=") && is(i + 2, "{") {
+                let end = Self::block_end(tokens, i + 2);
+                file.includes.extend(tokens[i + 3..end - 1].iter().cloned());
+                i = end;
+            } else if (token.is("VertexShader") || token.is("PixelShader"))
+                && is(i + 1, "=")
+                && is(i + 2, "{")
+            {
+                let end = Self::block_end(tokens, i + 2);
+                let names = if token.is("VertexShader") {
+                    &mut file.vertex_shaders
+                } else {
+                    &mut file.pixel_shaders
+                };
+                for j in i + 3..end {
+                    if tokens[j].is("MainCode") && j + 1 < end {
+                        names.push(tokens[j + 1].clone());
+                    }
+                }
+                i = end;
+            } else if token.is("Effect") && i + 1 < tokens.len() {
+                let mut effect = ShaderEffect {
+                    name: tokens[i + 1].clone(),
+                    vertex_shader: None,
+                    pixel_shader: None,
+                };
+                i += 2;
+                if is(i, "{") {
+                    let end = Self::block_end(tokens, i);
+                    for j in i + 1..end {
+                        if is(j + 1, "=") && j + 2 < end {
+                            if tokens[j].is("VertexShader") {
+                                effect.vertex_shader = Some(tokens[j + 2].clone());
+                            } else if tokens[j].is("PixelShader") {
+                                effect.pixel_shader = Some(tokens[j + 2].clone());
+                            }
+                        }
+                    }
+                    i = end;
+                }
+                file.effects.push(effect);
+            } else {
+                i += 1;
+            }
+        }
+        file
+    }
+}
+
+impl FileHandler<ShaderFile> for Shaders {
+    fn subpath(&self) -> PathBuf {
+        PathBuf::from("gfx/FX")
+    }
+
+    fn load_file(&self, entry: &FileEntry) -> Option<ShaderFile> {
+        let ext = entry.path().extension()?.to_string_lossy().to_lowercase();
+        if ext != "shader" && ext != "fxh" {
+            return None;
+        }
+
+        match read_to_string(entry.fullpath()) {
+            Ok(content) => {
+                let content = content.strip_prefix('\u{feff}').unwrap_or(&content);
+                Some(Self::parse(&Self::tokenize(content, Loc::from(entry))))
+            }
+            Err(e) => {
+                let msg = "could not read shader file";
+                let info = format!("{e:#}");
+                err(ErrorKey::ReadError).msg(msg).info(info).loc(entry).push();
+                None
+            }
+        }
+    }
+
+    fn handle_file(&mut self, entry: &FileEntry, file: ShaderFile) {
+        for effect in &file.effects {
+            self.effects.insert(effect.name.to_string(), effect.name.clone());
+        }
+        // Include paths always use forward slashes
+        let path = entry.path().strip_prefix("gfx/FX").unwrap_or(entry.path());
+        self.files.insert(path.to_string_lossy().replace('\\', "/"), file);
+    }
+}
@@ -0,0 +1,141 @@
+//! Summary statistics of a validation run, which can be saved as JSON and compared with the
+//! summary of a previous run.
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::fs::{read_to_string, write};
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+
+use crate::report::{Confidence, ReportCounts, Severity};
+
+/// How many of the files with the most reports to list in the summary.
+pub const TOP_FILES: usize = 10;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Summary {
+    /// Counts of the reports that were emitted.
+    /// The abbreviated reports about unused items, from `--unused`, are printed as they are found
+    /// and are not counted here.
+    pub reports: ReportCounts,
+    /// The files with the most reports, with their report counts.
+    pub top_files: Vec<(String, usize)>,
+    /// The number of files in the mod.
+    pub mod_files: usize,
+    /// The number of items of each type defined by the mod.
+    pub items: BTreeMap<String, usize>,
+    /// The wall-clock time taken by each phase of the run, in seconds.
+    pub phases: Vec<(String, f64)>,
+}
+
+impl Summary {
+    /// Read a summary that was saved with [`Summary::write`].
+    pub fn read(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_str(&read_to_string(path)?)?)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Describe how this summary differs from the `previous` one.
+    pub fn deltas(&self, previous: &Summary) -> String {
+        let mut lines = Vec::new();
+        push_delta(&mut lines, "reports", previous.reports.total, self.reports.total);
+        for severity in Severity::iter().rev() {
+            let name: &str = severity.into();
+            let (before, after) = (&previous.reports.severity, &self.reports.severity);
+            push_map_delta(&mut lines, name, name, before, after);
+        }
+        for confidence in Confidence::iter().rev() {
+            let name: &str = confidence.into();
+            let label = format!("{name} confidence");
+            let (before, after) = (&previous.reports.confidence, &self.reports.confidence);
+            push_map_delta(&mut lines, &label, name, before, after);
+        }
+        for key in union_keys(&previous.reports.key, &self.reports.key) {
+            let label = format!("({key})");
+            push_map_delta(&mut lines, &label, key, &previous.reports.key, &self.reports.key);
+        }
+        push_delta(&mut lines, "mod files", previous.mod_files, self.mod_files);
+        for item in union_keys(&previous.items, &self.items) {
+            let label = format!("{item} items");
+            push_map_delta(&mut lines, &label, item, &previous.items, &self.items);
+        }
+        if lines.is_empty() {
+            "No changes since the previous run.\n".to_string()
+        } else {
+            format!("Changes since the previous run:\n{}", lines.concat())
+        }
+    }
+}
+
+fn union_keys<'a>(a: &'a BTreeMap<String, usize>, b: &'a BTreeMap<String, usize>) -> Vec<&'a str> {
+    let mut keys: Vec<&str> = a.keys().chain(b.keys()).map(String::as_str).collect();
+    keys.sort_unstable();
+    keys.dedup();
+    keys
+}
+
+fn push_map_delta(
+    lines: &mut Vec<String>,
+    label: &str,
+    name: &str,
+    before: &BTreeMap<String, usize>,
+    after: &BTreeMap<String, usize>,
+) {
+    let before = before.get(name).copied().unwrap_or(0);
+    let after = after.get(name).copied().unwrap_or(0);
+    push_delta(lines, label, before, after);
+}
+
+fn push_delta(lines: &mut Vec<String>, name: &str, before: usize, after: usize) {
+    if before != after {
+        let sign = if after > before { "+" } else { "-" };
+        let diff = after.abs_diff(before);
+        lines.push(format!("  {name}: {before} -> {after} ({sign}{diff})\n"));
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        writeln!(f, "Reports: {}", self.reports.total)?;
+        let severities: Vec<String> = Severity::iter()
+            .rev()
+            .filter_map(|severity| {
+                let name: &str = severity.into();
+                self.reports.severity.get(name).map(|count| format!("{name} {count}"))
+            })
+            .collect();
+        writeln!(f, "  by severity: {}", severities.join(", "))?;
+        let confidences: Vec<String> = Confidence::iter()
+            .rev()
+            .filter_map(|confidence| {
+                let name: &str = confidence.into();
+                self.reports.confidence.get(name).map(|count| format!("{name} {count}"))
+            })
+            .collect();
+        writeln!(f, "  by confidence: {}", confidences.join(", "))?;
+        let mut keys: Vec<_> = self.reports.key.iter().collect();
+        keys.sort_by(|(a_key, a), (b_key, b)| b.cmp(a).then(a_key.cmp(b_key)));
+        let keys: Vec<String> = keys.iter().map(|(key, count)| format!("{key} {count}")).collect();
+        writeln!(f, "  by key: {}", keys.join(", "))?;
+        if !self.top_files.is_empty() {
+            writeln!(f, "Files with the most reports:")?;
+            for (file, count) in &self.top_files {
+                writeln!(f, "  {count:6} {file}")?;
+            }
+        }
+        writeln!(f, "Mod files: {}", self.mod_files)?;
+        let items: Vec<String> =
+            self.items.iter().map(|(item, count)| format!("{item} {count}")).collect();
+        writeln!(f, "Items defined by the mod: {}", items.join(", "))?;
+        let phases: Vec<String> =
+            self.phases.iter().map(|(phase, secs)| format!("{phase} {secs:.2}s")).collect();
+        writeln!(f, "Time: {}", phases.join(", "))
+    }
+}
@@ -28,6 +28,7 @@ use crate::scopes::{
 use crate::script_value::validate_script_value;
 use crate::token::{Loc, Token};
 use crate::tooltipped::Tooltipped;
+use crate::trigger_logic::check_trigger_logic;
 use crate::validate::{
     precheck_iterator_fields, validate_ifelse_sequence, validate_inside_iterator,
     validate_iterator_fields, ListType,
@@ -230,7 +231,7 @@ pub fn validate_trigger_internal(
         vd.ban_field("amount", || "`calc_true_if`");
     }
 
-    validate_ifelse_sequence(block, "trigger_if", "trigger_else_if", "trigger_else");
+    validate_ifelse_sequence(block, data, "trigger_if", "trigger_else_if", "trigger_else");
 
     vd.unknown_fields_any_cmp(|key, cmp, bv| {
         if key.is("value") {
@@ -302,6 +303,7 @@ pub fn validate_trigger_internal(
         });
     }
 
+    check_trigger_logic(caller, block, data);
     side_effects
 }
 
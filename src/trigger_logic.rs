@@ -0,0 +1,354 @@
+//! A simple satisfiability analysis of trigger blocks.
+//!
+//! The conditions that must all hold for a trigger block to be true are collected as literals,
+//! such as `is_female = yes` or `age > 50`. Pairs of literals are then compared to find
+//! contradictions, and the literals of `if` / `else_if` limits are compared to find branches that
+//! can never be reached. Anything more complicated than a literal is ignored.
+
+use crate::block::{Block, BlockItem, Comparator, Eq::*, Field, BV};
+use crate::everything::Everything;
+use crate::lowercase::Lowercase;
+use crate::report::{warn, ErrorKey};
+use crate::script_value::eval_script_value;
+use crate::token::Token;
+use crate::trigger::{scope_trigger, Trigger};
+
+/// The part of a [`Literal`] that says what is being tested.
+#[derive(Clone, Debug)]
+enum Atom<'a> {
+    /// A yes/no trigger, including scripted triggers without parameters. The bool is the value
+    /// it must have.
+    Bool(bool),
+    /// A numeric comparison against a constant, such as `age > 50`.
+    Number(Comparator, f64),
+    /// An equality test against an item or a scope, such as `has_trait = brave`. The bool is true
+    /// if the test must fail.
+    Equals(&'a Token, bool),
+}
+
+/// One condition that must hold for a trigger block to be true.
+#[derive(Clone, Debug)]
+struct Literal<'a> {
+    key: &'a Token,
+    atom: Atom<'a>,
+}
+
+/// A range of numbers, used for comparing numeric literals.
+#[derive(Clone, Copy, Debug)]
+struct Interval {
+    low: f64,
+    low_open: bool,
+    high: f64,
+    high_open: bool,
+}
+
+impl Interval {
+    /// Return the numbers allowed by a comparison, or `None` for `!=`.
+    fn new(cmp: Comparator, value: f64) -> Option<Self> {
+        let (low, low_open, high, high_open) = match cmp {
+            Comparator::Equals(_) => (value, false, value, false),
+            Comparator::NotEquals => return None,
+            Comparator::LessThan => (f64::NEG_INFINITY, true, value, true),
+            Comparator::AtMost => (f64::NEG_INFINITY, true, value, false),
+            Comparator::GreaterThan => (value, true, f64::INFINITY, true),
+            Comparator::AtLeast => (value, false, f64::INFINITY, true),
+        };
+        Some(Self { low, low_open, high, high_open })
+    }
+
+    #[allow(clippy::float_cmp)] // exact comparison is intended here
+    fn contains(self, value: f64) -> bool {
+        (value > self.low || (value == self.low && !self.low_open))
+            && (value < self.high || (value == self.high && !self.high_open))
+    }
+
+    #[allow(clippy::float_cmp)] // exact comparison is intended here
+    fn is_point(self, value: f64) -> bool {
+        self.low == value && self.high == value && !self.low_open && !self.high_open
+    }
+
+    #[allow(clippy::float_cmp)] // exact comparison is intended here
+    fn is_subset_of(self, other: Self) -> bool {
+        (self.low > other.low || (self.low == other.low && (self.low_open || !other.low_open)))
+            && (self.high < other.high
+                || (self.high == other.high && (self.high_open || !other.high_open)))
+    }
+
+    #[allow(clippy::float_cmp)] // exact comparison is intended here
+    fn is_disjoint_from(self, other: Self) -> bool {
+        let (low, low_open) = if self.low == other.low {
+            (self.low, self.low_open || other.low_open)
+        } else if self.low > other.low {
+            (self.low, self.low_open)
+        } else {
+            (other.low, other.low_open)
+        };
+        let (high, high_open) = if self.high == other.high {
+            (self.high, self.high_open || other.high_open)
+        } else if self.high < other.high {
+            (self.high, self.high_open)
+        } else {
+            (other.high, other.high_open)
+        };
+        low > high || (low == high && (low_open || high_open))
+    }
+}
+
+fn negate_comparator(cmp: Comparator) -> Comparator {
+    match cmp {
+        Comparator::Equals(_) => Comparator::NotEquals,
+        Comparator::NotEquals => Comparator::Equals(Single),
+        Comparator::LessThan => Comparator::AtLeast,
+        Comparator::AtMost => Comparator::GreaterThan,
+        Comparator::GreaterThan => Comparator::AtMost,
+        Comparator::AtLeast => Comparator::LessThan,
+    }
+}
+
+impl<'a> Literal<'a> {
+    /// Make a literal out of a trigger field, if it is simple enough.
+    /// `negated` is true if the field has to be false rather than true.
+    fn new(field: &'a Field, data: &Everything, negated: bool) -> Option<Self> {
+        let Field(key, cmp, bv) = field;
+        let is_eq = matches!(cmp, Comparator::Equals(Single | Double));
+        let yes_no = bv.get_value().and_then(|token| {
+            if token.lowercase_is("yes") {
+                Some(true)
+            } else if token.lowercase_is("no") {
+                Some(false)
+            } else {
+                None
+            }
+        });
+
+        if let Some(trigger) = data.get_trigger(key) {
+            if !is_eq || !trigger.macro_parms().is_empty() {
+                return None;
+            }
+            return Some(Self { key, atom: Atom::Bool(yes_no? != negated) });
+        }
+
+        let atom = match scope_trigger(key, data)?.1 {
+            Trigger::Boolean if is_eq => Atom::Bool(yes_no? != negated),
+            Trigger::CompareValue => Self::number_atom(*cmp, bv, data, negated)?,
+            #[cfg(feature = "ck3")]
+            Trigger::CompareValueWarnEq => Self::number_atom(*cmp, bv, data, negated)?,
+            Trigger::Item(_)
+            | Trigger::Scope(_)
+            | Trigger::ScopeOkThis(_)
+            | Trigger::ScopeOrItem(_, _)
+            | Trigger::Choice(_)
+                if is_eq =>
+            {
+                Atom::Equals(bv.get_value()?, negated)
+            }
+            _ => return None,
+        };
+        Some(Self { key, atom })
+    }
+
+    fn number_atom(cmp: Comparator, bv: &BV, data: &Everything, negated: bool) -> Option<Atom<'a>> {
+        if matches!(cmp, Comparator::Equals(Question)) {
+            return None;
+        }
+        let value = eval_script_value(bv, data)?.exact_value()?;
+        let cmp = if negated { negate_comparator(cmp) } else { cmp };
+        Some(Atom::Number(cmp, value))
+    }
+
+    /// Return true iff this literal and `other` can never both be true.
+    fn contradicts(&self, other: &Self) -> bool {
+        if !self.key.as_str().eq_ignore_ascii_case(other.key.as_str()) {
+            return false;
+        }
+        match (&self.atom, &other.atom) {
+            (Atom::Bool(a), Atom::Bool(b)) => a != b,
+            (Atom::Equals(a, a_neg), Atom::Equals(b, b_neg)) => a.is(b.as_str()) && a_neg != b_neg,
+            (Atom::Number(a_cmp, a), Atom::Number(b_cmp, b)) => {
+                match (Interval::new(*a_cmp, *a), Interval::new(*b_cmp, *b)) {
+                    (Some(a_range), Some(b_range)) => a_range.is_disjoint_from(b_range),
+                    // `!= x` only contradicts `= x`
+                    (Some(a_range), None) => a_range.is_point(*b),
+                    (None, Some(b_range)) => b_range.is_point(*a),
+                    (None, None) => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Return true iff `other` is true whenever this literal is true.
+    fn implies(&self, other: &Self) -> bool {
+        if !self.key.as_str().eq_ignore_ascii_case(other.key.as_str()) {
+            return false;
+        }
+        match (&self.atom, &other.atom) {
+            (Atom::Bool(a), Atom::Bool(b)) => a == b,
+            (Atom::Equals(a, a_neg), Atom::Equals(b, b_neg)) => a.is(b.as_str()) && a_neg == b_neg,
+            (Atom::Number(a_cmp, a), Atom::Number(b_cmp, b)) => {
+                match (Interval::new(*a_cmp, *a), Interval::new(*b_cmp, *b)) {
+                    (Some(a), Some(b)) => a.is_subset_of(b),
+                    (Some(a), None) => !a.contains(*b),
+                    (None, Some(_)) => false,
+                    #[allow(clippy::float_cmp)] // exact comparison is intended here
+                    (None, None) => a == b,
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+/// For a control block such as `AND` or `NOR`, return the `negated` value to collect its items
+/// with, or `None` if its items can't be collected as literals in the current `negated` context.
+fn items_negated(key: &Lowercase, block: &Block, negated: bool) -> Option<bool> {
+    match key.as_str() {
+        "and" if !negated => Some(false),
+        "nor" | "all_false" if !negated => Some(true),
+        // not (a or b) is (not a) and (not b)
+        "or" if negated => Some(true),
+        "nand" | "any_false" if negated => Some(false),
+        // `NOT` with more than one item is best avoided, so don't guess what it does
+        "not" if block.iter_items().count() == 1 => Some(!negated),
+        _ => None,
+    }
+}
+
+/// Collect the literals that must all hold for `block` to be true, or if `negated` is true, the
+/// literals that must all hold for every item in `block` to be false.
+///
+/// Returns false if some items in the block could not be turned into literals. The collected
+/// literals are then still necessary but no longer sufficient for the block's outcome.
+fn collect<'a>(
+    block: &'a Block,
+    data: &Everything,
+    negated: bool,
+    literals: &mut Vec<Literal<'a>>,
+) -> bool {
+    let mut complete = true;
+    for item in block.iter_items() {
+        let BlockItem::Field(field) = item else {
+            complete = false;
+            continue;
+        };
+        let key = Lowercase::new(field.key().as_str());
+        if key == "always" && field.bv().get_value().is_some_and(|t| t.is("yes") != negated) {
+            // It holds, so there is nothing to collect
+            continue;
+        }
+        if let BV::Block(block) = field.bv() {
+            if let Some(items_negated) = items_negated(&key, block, negated) {
+                complete &= collect(block, data, items_negated, literals);
+                continue;
+            }
+            if key == "or" && !negated && is_tautology(block, data) {
+                continue;
+            }
+        }
+        if let Some(literal) = Literal::new(field, data, negated) {
+            literals.push(literal);
+        } else {
+            complete = false;
+        }
+    }
+    complete
+}
+
+/// Return true iff the `OR` block is always true because it contains a condition and its opposite.
+fn is_tautology(block: &Block, data: &Everything) -> bool {
+    let mut literals = Vec::new();
+    collect(block, data, true, &mut literals);
+    literals.iter().enumerate().any(|(i, a)| literals[i + 1..].iter().any(|b| a.contradicts(b)))
+}
+
+/// Check a trigger block for conditions that contradict each other, and check an `OR` block for
+/// being always true.
+///
+/// `caller` is the key that opened the block, as for [`validate_trigger_internal`](crate::trigger::validate_trigger_internal).
+pub fn check_trigger_logic(caller: &Lowercase, block: &Block, data: &Everything) {
+    let negated = match caller.as_str() {
+        "or" => {
+            if is_tautology(block, data) {
+                let msg = "this `OR` is always true";
+                let info = "it contains a condition as well as its opposite";
+                warn(ErrorKey::Logic).msg(msg).info(info).loc(block).push();
+            }
+            return;
+        }
+        "nor" | "all_false" => true,
+        // These are not conjunctions
+        "not" | "nand" | "any_false" | "calc_true_if" => return,
+        _ => false,
+    };
+
+    let mut literals = Vec::new();
+    collect(block, data, negated, &mut literals);
+    for (i, earlier) in literals.iter().enumerate() {
+        if let Some(later) = literals[i + 1..].iter().find(|later| earlier.contradicts(later)) {
+            let msg = "these conditions contradict each other";
+            let info = "so this trigger can never be true";
+            warn(ErrorKey::Logic)
+                .msg(msg)
+                .info(info)
+                .loc(later.key)
+                .loc(earlier.key, "contradicts this")
+                .push();
+        }
+    }
+}
+
+/// Check a sequence of `if`, `else_if` and `else` blocks for limits that are always true, and for
+/// branches that can never be reached because an earlier branch in the sequence covers them.
+pub fn check_ifelse_logic(
+    block: &Block,
+    data: &Everything,
+    key_if: &str,
+    key_elseif: &str,
+    key_else: &str,
+) {
+    // The keys and limits of the earlier branches in the current sequence, if they were fully
+    // understood.
+    let mut earlier: Vec<(&Token, Vec<Literal>)> = Vec::new();
+    for (key, block) in block.iter_definitions() {
+        if key.is(key_if) {
+            earlier.clear();
+        } else if !key.is(key_elseif) && !key.is(key_else) {
+            earlier.clear();
+            continue;
+        }
+
+        let mut literals = Vec::new();
+        let mut complete = false;
+        if let Some(limit) = block.get_field_block("limit") {
+            complete = collect(limit, data, false, &mut literals);
+            if complete && literals.is_empty() {
+                let msg = "this `limit` is always true";
+                let limit_key = block.get_key("limit").unwrap_or(key);
+                warn(ErrorKey::Logic).msg(msg).loc(limit_key).push();
+            }
+        } else if !key.is(key_else) {
+            earlier.clear();
+            continue;
+        }
+
+        if !key.is(key_if) {
+            // A branch is unreachable if the limit of an earlier branch holds whenever its own limit holds.
+            if let Some((earlier_key, _)) = earlier.iter().find(|(_, earlier_literals)| {
+                earlier_literals.iter().all(|e| literals.iter().any(|l| l.implies(e)))
+            }) {
+                let msg = format!("this `{key}` can never be reached");
+                let info = "an earlier branch is always taken whenever this one would be";
+                warn(ErrorKey::Logic)
+                    .msg(msg)
+                    .info(info)
+                    .loc(key)
+                    .loc(*earlier_key, "earlier branch")
+                    .push();
+            }
+        }
+
+        if complete {
+            earlier.push((key, literals));
+        }
+    }
+}
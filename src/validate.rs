@@ -14,10 +14,14 @@ use crate::everything::Everything;
 use crate::game::Game;
 use crate::item::Item;
 use crate::lowercase::Lowercase;
+#[cfg(any(feature = "ck3", feature = "vic3"))]
+use crate::report::untidy;
 use crate::report::{err, error, fatal, old_warn, report, warn, Confidence, ErrorKey, Severity};
 #[cfg(feature = "ck3")]
 use crate::scopes::Scopes;
 use crate::scopes::{scope_prefix, scope_to_scope};
+#[cfg(any(feature = "ck3", feature = "vic3"))]
+use crate::script_value::{eval_script_value, ValueRange};
 use crate::script_value::{validate_non_dynamic_script_value, validate_script_value};
 use crate::token::Token;
 use crate::tooltipped::Tooltipped;
@@ -27,6 +31,7 @@ use crate::trigger::{
     partition, validate_argument, validate_argument_scope, validate_inscopes, validate_trigger,
     validate_trigger_internal, warn_not_first, Part, PartFlags,
 };
+use crate::trigger_logic::check_ifelse_logic;
 use crate::validator::Validator;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -607,6 +612,38 @@ pub fn validate_ai_chance(bv: &BV, data: &Everything, sc: &mut ScopeContext) {
         BV::Value(t) => _ = t.expect_number(),
         BV::Block(b) => validate_modifiers_with_base(b, data, sc),
     }
+
+    // An ai_chance is a relative weight rather than a percentage, so only 0 is suspicious here.
+    // It's often deliberate, so the report is weak.
+    #[cfg(any(feature = "ck3", feature = "vic3"))]
+    if eval_ai_chance(bv, data).is_some_and(|range| range.max <= 0.0) {
+        let msg = "this AI chance is always 0";
+        let info = "the AI will never pick this";
+        untidy(ErrorKey::Range).weak().msg(msg).info(info).loc(bv).push();
+    }
+}
+
+/// Evaluate an `ai_chance` that has no modifiers, only a `base` and optional `add` and `factor`.
+#[cfg(any(feature = "ck3", feature = "vic3"))] // imperator doesn't validate its events yet
+fn eval_ai_chance(bv: &BV, data: &Everything) -> Option<ValueRange> {
+    let block = match bv {
+        BV::Value(_) => return eval_script_value(bv, data),
+        BV::Block(block) => block,
+    };
+    let mut value = ValueRange::exact(0.0);
+    for field in block.iter_fields() {
+        let term = eval_script_value(field.bv(), data)?;
+        if field.key().is("base") {
+            value = term;
+        } else if field.key().is("add") {
+            value = value.add(term);
+        } else if field.key().is("factor") {
+            value = value.multiply(term);
+        } else {
+            return None;
+        }
+    }
+    Some(value)
 }
 
 /// Validate the left-hand part of a `target = { target_scope }` block.
@@ -675,7 +712,13 @@ pub fn validate_scope_chain(
     true
 }
 
-pub fn validate_ifelse_sequence(block: &Block, key_if: &str, key_elseif: &str, key_else: &str) {
+pub fn validate_ifelse_sequence(
+    block: &Block,
+    data: &Everything,
+    key_if: &str,
+    key_elseif: &str,
+    key_else: &str,
+) {
     let mut seen_if = false;
     for (key, block) in block.iter_definitions() {
         if key.is(key_if) {
@@ -701,6 +744,7 @@ pub fn validate_ifelse_sequence(block: &Block, key_if: &str, key_elseif: &str, k
         }
         seen_if = false;
     }
+    check_ifelse_logic(block, data, key_if, key_elseif, key_else);
 }
 
 pub fn validate_numeric_range(
@@ -0,0 +1,186 @@
+//! A thin layer over the filesystem that lets mods be read directly from `.zip` archives.
+//!
+//! Files inside an archive get a full path that is the archive's own path joined with the file's
+//! path inside the archive, for example `/tmp/MyMod.zip/MyMod/common/traits/00_traits.txt`.
+//! Code that reads file contents by full path should go through the functions here, so that such
+//! paths are resolved correctly. Paths that are not inside an archive go to the real filesystem.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+
+use fnv::FnvHashMap;
+#[cfg(any(feature = "ck3", feature = "vic3"))]
+use image::{DynamicImage, ImageFormat, ImageResult};
+use once_cell::sync::Lazy;
+use zip::result::ZipResult;
+use zip::ZipArchive;
+
+static ARCHIVES: Lazy<RwLock<Vec<Archive>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+/// A `.zip` file that has been opened for reading.
+#[derive(Debug)]
+struct Archive {
+    /// The filesystem path of the `.zip` file itself.
+    path: PathBuf,
+    /// The archive reader. It needs `&mut` access to extract files, so it's behind a mutex.
+    zip: Mutex<ZipArchive<File>>,
+    /// Maps the path of each file in the archive (with `/` separators) to its name as stored in
+    /// the archive. They differ when the archive was made with backslash separators.
+    names: FnvHashMap<String, String>,
+}
+
+impl Archive {
+    fn open(path: &Path) -> ZipResult<Self> {
+        let zip = ZipArchive::new(File::open(path)?)?;
+        let names = zip
+            .file_names()
+            .filter(|name| !name.ends_with('/') && !name.ends_with('\\'))
+            .map(|name| (name.replace('\\', "/"), name.to_string()))
+            .collect();
+        Ok(Self { path: path.to_path_buf(), zip: Mutex::new(zip), names })
+    }
+
+    /// Convert a full path to a path inside this archive, if it is one.
+    fn inner_name(&self, fullpath: &Path) -> Option<String> {
+        let inner = fullpath.strip_prefix(&self.path).ok()?;
+        let mut parts = Vec::new();
+        for component in inner.components() {
+            match component {
+                Component::Normal(part) => parts.push(part.to_string_lossy()),
+                Component::CurDir => (),
+                _ => return None,
+            }
+        }
+        Some(parts.join("/"))
+    }
+
+    fn read(&self, name: &str, limit: Option<u64>) -> io::Result<Vec<u8>> {
+        let Some(raw_name) = self.names.get(name) else {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "file not found in archive"));
+        };
+        let mut zip = self.zip.lock().unwrap();
+        let mut file = zip.by_name(raw_name)?;
+        let mut bytes = Vec::new();
+        if let Some(limit) = limit {
+            file.take(limit).read_to_end(&mut bytes)?;
+        } else {
+            file.read_to_end(&mut bytes)?;
+        }
+        Ok(bytes)
+    }
+}
+
+/// Open a `.zip` file containing a mod, so that its files can be read through this module.
+///
+/// Returns the full path of the mod's root inside the archive. That is the shallowest directory
+/// that contains a `descriptor.mod` or `.metadata/metadata.json` file. If there is none, it's the
+/// single top-level directory in the archive if there is one, or else the top of the archive.
+#[allow(clippy::missing_panics_doc)] // only panics on poisoned lock
+pub fn open_mod_archive(path: &Path) -> ZipResult<PathBuf> {
+    let archive = Archive::open(path)?;
+    let marked_root = archive
+        .names
+        .keys()
+        .filter_map(|name| {
+            name.strip_suffix("descriptor.mod")
+                .or_else(|| name.strip_suffix(".metadata/metadata.json"))
+        })
+        .filter(|dir| dir.is_empty() || dir.ends_with('/'))
+        .min_by_key(|dir| (dir.matches('/').count(), *dir))
+        .map(|dir| dir.trim_end_matches('/'));
+    let root = if let Some(dir) = marked_root {
+        path.join(dir)
+    } else {
+        let mut top_dirs =
+            archive.names.keys().map(|name| name.split_once('/').map(|(top, _)| top));
+        match top_dirs.next() {
+            Some(Some(top)) if top_dirs.all(|other| other == Some(top)) => path.join(top),
+            _ => path.to_path_buf(),
+        }
+    };
+    ARCHIVES.write().unwrap().push(archive);
+    Ok(root)
+}
+
+/// Run `f` on the archive containing `fullpath` and the file's name within it.
+/// Returns `None` if `fullpath` is not inside an opened archive.
+fn with_archive<T, F>(fullpath: &Path, f: F) -> Option<T>
+where
+    F: FnOnce(&Archive, &str) -> T,
+{
+    let archives = ARCHIVES.read().unwrap();
+    for archive in archives.iter() {
+        if let Some(name) = archive.inner_name(fullpath) {
+            return Some(f(archive, &name));
+        }
+    }
+    None
+}
+
+/// List the full paths of all the files under `dir`, if `dir` is inside an opened archive.
+/// Returns `None` if it's a normal directory.
+pub(crate) fn archive_files_under(dir: &Path) -> Option<Vec<PathBuf>> {
+    with_archive(dir, |archive, prefix| {
+        let mut files: Vec<PathBuf> = archive
+            .names
+            .keys()
+            .filter(|name| {
+                prefix.is_empty()
+                    || name.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('/'))
+            })
+            .map(|name| archive.path.join(name))
+            .collect();
+        files.sort();
+        files
+    })
+}
+
+/// Check if `path` is a file or directory, either on disk or inside an opened archive.
+pub fn path_exists(path: &Path) -> bool {
+    with_archive(path, |archive, name| {
+        name.is_empty()
+            || archive.names.keys().any(|other| {
+                other == name || other.strip_prefix(name).is_some_and(|rest| rest.starts_with('/'))
+            })
+    })
+    .unwrap_or_else(|| path.exists())
+}
+
+/// Check if `path` is a file, either on disk or inside an opened archive.
+pub fn is_file(path: &Path) -> bool {
+    with_archive(path, |archive, name| archive.names.contains_key(name))
+        .unwrap_or_else(|| path.is_file())
+}
+
+/// Read the whole contents of a file.
+pub fn read(fullpath: &Path) -> io::Result<Vec<u8>> {
+    with_archive(fullpath, |archive, name| archive.read(name, None))
+        .unwrap_or_else(|| std::fs::read(fullpath))
+}
+
+/// Read at most `len` bytes from the start of a file.
+pub fn read_prefix(fullpath: &Path, len: usize) -> io::Result<Vec<u8>> {
+    // usize to u64 is lossless on all supported platforms
+    let limit = len as u64;
+    with_archive(fullpath, |archive, name| archive.read(name, Some(limit))).unwrap_or_else(|| {
+        let mut bytes = Vec::new();
+        File::open(fullpath)?.take(limit).read_to_end(&mut bytes)?;
+        Ok(bytes)
+    })
+}
+
+/// Open an image file, like `image::open` does.
+#[cfg(any(feature = "ck3", feature = "vic3"))]
+pub(crate) fn open_image(fullpath: &Path) -> ImageResult<DynamicImage> {
+    let format = ImageFormat::from_path(fullpath)?;
+    image::load_from_memory_with_format(&read(fullpath)?, format)
+}
+
+/// Read the whole contents of a file, which must be valid UTF-8.
+pub fn read_to_string(fullpath: &Path) -> io::Result<String> {
+    String::from_utf8(read(fullpath)?).map_err(|_| {
+        io::Error::new(io::ErrorKind::InvalidData, "stream did not contain valid UTF-8")
+    })
+}
@@ -80,6 +80,10 @@ impl Vic3Events {
         self.events.values().map(|item| &item.key)
     }
 
+    pub fn iter_definitions(&self) -> impl Iterator<Item = (&Token, &Block)> {
+        self.events.values().map(|item| (&item.key, &item.block))
+    }
+
     pub fn validate(&self, data: &Everything) {
         for item in self.events.values() {
             item.validate(data);
@@ -8,6 +8,7 @@ use crate::fileset::{FileEntry, FileHandler};
 use crate::item::Item;
 use crate::report::{err, report, ErrorKey, Severity};
 use crate::token::Token;
+use crate::vfs::open_image;
 
 #[derive(Clone, Debug, Default)]
 pub struct Vic3Provinces {
@@ -59,7 +60,7 @@ impl FileHandler<DynamicImage> for Vic3Provinces {
 
     fn load_file(&self, entry: &FileEntry) -> Option<DynamicImage> {
         if entry.path().components().count() == 2 {
-            let img = match image::open(entry.fullpath()) {
+            let img = match open_image(entry.fullpath()) {
                 Ok(img) => img,
                 Err(e) => {
                     let msg = format!("could not read `{}`: {e:#}", entry.path().display());
@@ -0,0 +1,33 @@
+﻿Includes = {
+	"test/test_common.fxh"
+}
+
+PixelShader =
+{
+	MainCode PS_Test
+	{
+		Input = "VS_OUTPUT_PDX_GUI"
+		Output = "PDX_COLOR"
+		Code
+		[[
+			// Effect NotAnEffect
+			PDX_MAIN
+			{
+				return float4( 1, 1, 1, 1 );
+			}
+		]]
+	}
+}
+
+# Effect AlsoNotAnEffect
+Effect TestEffect // the real one
+{
+	VertexShader = "VS_Default"
+	PixelShader = "PS_Test"
+}
+
+Effect TestMissingPixelShader
+{
+	VertexShader = "VS_Default"
+	PixelShader = "PS_Missing"
+}
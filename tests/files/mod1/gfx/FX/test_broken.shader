@@ -0,0 +1,9 @@
+﻿Includes = {
+	"test/no_such_file.fxh"
+}
+
+Effect TestBrokenEffect
+{
+	VertexShader = "VS_Unknown"
+	PixelShader = "PS_Unknown"
+}
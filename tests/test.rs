@@ -1,8 +1,16 @@
 use lazy_static::lazy_static;
-use std::path::PathBuf;
+use std::fs::{read, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
-use tiger_lib::{take_reports, Everything, LogReport};
+use walkdir::WalkDir;
+use zip::ZipWriter;
+
+use tiger_lib::{
+    collect_abbreviated_reports, emit_reports_html, open_mod_archive, set_output_file,
+    take_reports, Confidence, Everything, LogReport, Severity, Summary,
+};
 
 lazy_static! {
     static ref TEST_MUTEX: Mutex<()> = Mutex::new(());
@@ -21,6 +29,38 @@ fn check_mod_helper(modname: &str) -> Vec<LogReport> {
     take_reports()
 }
 
+fn check_zipped_mod_helper(modname: &str) -> Vec<LogReport> {
+    let _guard = TEST_MUTEX.lock().unwrap();
+
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let mod_root = PathBuf::from(format!("tests/files/{}", modname));
+    let zip_path = std::env::temp_dir().join(format!("tiger-test-{}.zip", modname));
+    write_zip(&mod_root, &zip_path, modname);
+
+    let mod_root = open_mod_archive(&zip_path).unwrap();
+    let mut everything = Everything::new(Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.load_all();
+    everything.validate_all();
+    std::fs::remove_file(&zip_path).unwrap();
+
+    take_reports()
+}
+
+/// Pack the files under `dir` into a zip file, inside a top-level folder named `prefix`.
+fn write_zip(dir: &Path, zip_path: &Path, prefix: &str) {
+    let mut zip = ZipWriter::new(File::create(zip_path).unwrap());
+    for entry in WalkDir::new(dir) {
+        let entry = entry.unwrap();
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let inner = entry.path().strip_prefix(dir).unwrap().to_string_lossy().replace('\\', "/");
+        zip.start_file(format!("{prefix}/{inner}"), Default::default()).unwrap();
+        zip.write_all(&read(entry.path()).unwrap()).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
 fn take_report_contains(
     vec: &mut Vec<LogReport>,
     pathname: &str,
@@ -110,6 +150,188 @@ fn test_mod1() {
     let events = "events/test-script-values.txt";
     let report = take_report_contains(&mut reports, events, "`else` with a `limit`");
     report.expect("scriptvalue else with a limit");
+    let report = take_report(&mut reports, events, "divide by zero");
+    report.expect("scriptvalue division by constant zero");
+    let report = take_report(&mut reports, events, "`min` is larger than `max`");
+    report.expect("scriptvalue crossed min and max");
+    let report =
+        take_report(&mut reports, events, "`min` is always larger than `max` in this range");
+    report.expect("scriptvalue inverted range");
+    let report = take_report(&mut reports, events, "`chance` is always more than 100");
+    report.expect("scriptvalue folded chance");
+    let report = take_report_contains(&mut reports, events, "has a weight of 0");
+    report.expect("random_list zero weight");
+    for line in [82, 86] {
+        let report = take_report(&mut reports, events, "`chance` is always more than 100");
+        let report = report.expect("chance folded through a script value or define");
+        assert!(report.pointers[0].loc.line == line);
+    }
+    let msg = "this `random_list` entry has a weight of 0 and its modifiers never raise it";
+    for line in [90, 97] {
+        let report = take_report(&mut reports, events, msg);
+        let report = report.expect("random_list modifiers folded through a script value or define");
+        assert!(report.pointers[0].loc.line == line);
+    }
+    for line in [109, 116] {
+        let report = take_report(&mut reports, events, "this AI chance is always 0");
+        let report = report.expect("ai_chance folded through a script value");
+        assert!(report.pointers[0].loc.line == line);
+    }
+
+    let events = "events/test-trigger-logic.txt";
+    for _ in 0..3 {
+        let report = take_report(&mut reports, events, "these conditions contradict each other");
+        report.expect("trigger contradiction");
+    }
+    let report = take_report(&mut reports, events, "this `OR` is always true");
+    report.expect("trigger tautology");
+    let report = take_report(&mut reports, events, "this `limit` is always true");
+    report.expect("always true limit");
+    let report = take_report(&mut reports, events, "this `else_if` can never be reached");
+    let report = report.expect("subsumed else_if");
+    assert!(report.pointers[1].loc.line == 29);
+    let report = take_report(&mut reports, events, "this `else` can never be reached");
+    report.expect("else after always true limit");
+
+    let events = "events/test-call-sites.txt";
+    let report = take_report_contains(&mut reports, events, "but in character scope at 4 of its 5");
+    let report = report.expect("scripted effect call site scope majority");
+    assert!(report.pointers[0].loc.line == 34);
+    let report = take_report_contains(&mut reports, events, "uses scope:target");
+    let report = report.expect("scripted effect call site named scope majority");
+    assert!(report.pointers[0].loc.line == 46);
+    for name in ["test_title_effect", "test_macro_effect"] {
+        let msg = format!(
+            "`{name}` expects scope:target to be landed title but scope:target seems to be character"
+        );
+        let report = take_report(&mut reports, events, &msg);
+        report.expect("scripted effect call site named scope type");
+    }
+    let effects = "common/scripted_effects/test-effects.txt";
+    let msg = "`holder` is for landed title but scope seems to be character";
+    for line in [13, 19] {
+        let report = take_report(&mut reports, effects, msg);
+        let report = report.expect("scripted effect body validated with call site scopes");
+        assert!(report.pointers[0].loc.line == line);
+        assert!(report.pointers[1].loc.pathname() == Path::new(events));
+    }
+
+    let report = take_report(
+        &mut reports,
+        "common/scripted_effects/test-loops.txt",
+        "scripted effect `test_loop_effect` calls itself",
+    );
+    let report = report.expect("scripted effect recursion");
+    assert!(report.pointers[0].loc.line == 4);
+    let report = take_report(
+        &mut reports,
+        "common/scripted_triggers/test-loops.txt",
+        "scripted trigger `test_loop_trigger` calls itself through a loop",
+    );
+    let report = report.expect("scripted trigger and script value recursion");
+    assert!(report.pointers.len() == 2);
+    let report = take_report(
+        &mut reports,
+        "common/on_action/test-loops.txt",
+        "on_action `test_loop_on_action` calls itself through a loop",
+    );
+    let report = report.expect("on_action fallback loop");
+    assert!(report.pointers[1].loc.line == 6);
+
+    let gui = "gui/test-datacontext.gui";
+    let report = take_report(&mut reports, gui, "no Character datacontext here");
+    let report = report.expect("gui datacontext type");
+    assert!(report.pointers[0].loc.line == 7);
+    // The instance's own datacontext applies over the one from its type, and also to the
+    // contents of its type.
+    let gui = "gui/test-datacontext-type.gui";
+    let report = take_report(&mut reports, gui, "no Character datacontext here");
+    assert!(report.is_none());
+    let gui = "gui/test-blockoverride.gui";
+    let report = take_report(&mut reports, gui, "did not find block for blockoverride `missing`");
+    report.expect("gui unmatched blockoverride");
+    let gui = "gui/test-properties.gui";
+    let report = take_report(&mut reports, gui, "shader effect NotAnEffect not defined in gfx/FX/");
+    report.expect("gui effectname");
+    let report = take_report(&mut reports, gui, "no state named `c` in this widget");
+    report.expect("gui state next");
+    let gui = "gui/test-format.gui";
+    let report = take_report(&mut reports, gui, "text format missing_format not defined in gui/");
+    let report = report.expect("gui markup textformat");
+    assert!(report.confidence == Confidence::Weak);
+    let report = take_report(&mut reports, gui, "default_format should start with `#`");
+    report.expect("gui default_format");
+    let report = take_report(&mut reports, gui, "`#!` without a `#markup` to close");
+    report.expect("gui unbalanced markup end");
+    let report = take_report(&mut reports, gui, "`#bold` is not closed with `#!`");
+    report.expect("gui unclosed markup");
+    let gui = "gui/test-scripted-gui.gui";
+    let msg = "`other` is not in the `saved_scopes` of scripted gui `test_sgui`";
+    let report = take_report(&mut reports, gui, msg);
+    report.expect("scripted gui AddScope");
+    let report = take_report(&mut reports, gui, "scripted gui `test_sgui` has no `is_valid`");
+    report.expect("scripted gui IsValid");
+    let msg = "`SetRoot` is given landed title but `test_sgui` expects character";
+    let report = take_report(&mut reports, gui, msg);
+    let report = report.expect("scripted gui datacontext SetRoot");
+    assert!(report.pointers[0].loc.line == 9);
+    let gui = "gui/test-unused.gui";
+    let report = take_report_contains(&mut reports, gui, "there is no textformat of that name");
+    report.expect("textformat override without original");
+    let gui = "gui/test-frames.gui";
+    let report = take_report(&mut reports, gui, "frame 5 is out of range");
+    let report = report.expect("gui frame range");
+    assert!(report.info.as_deref() == Some("the texture has 4 frames of 16x32"));
+    let msg = "framesize 24x32 does not divide the texture size of 64x32";
+    let report = take_report(&mut reports, gui, msg);
+    report.expect("gui framesize");
+    let msg = "spriteborder does not fit inside the 64x32 texture";
+    let report = take_report(&mut reports, gui, msg);
+    report.expect("gui spriteborder");
+    let msg = "spriteborder sides do not fit inside the 64x32 texture";
+    let report = take_report(&mut reports, gui, msg);
+    report.expect("gui spriteborder sides");
+    let shader = "gfx/FX/test.shader";
+    let msg = "pixel shader PS_Missing not defined in this file or its includes";
+    let report = take_report(&mut reports, shader, msg);
+    let report = report.expect("shader effect without pixel shader");
+    assert!(report.pointers[0].loc.line == 32);
+    assert!(report.confidence == Confidence::Weak);
+    let shader = "gfx/FX/test_broken.shader";
+    let msg = "shader include test/no_such_file.fxh not found in gfx/FX/";
+    let report = take_report(&mut reports, shader, msg);
+    let report = report.expect("broken shader include");
+    assert!(report.confidence == Confidence::Weak);
+    let gui = "gui/test-shaders.gui";
+    let msg = "shader effect TestBrokenEffect not defined in gfx/FX/test.shader or its includes";
+    let report = take_report(&mut reports, gui, msg);
+    report.expect("gui effectname in shaderfile");
+    let asset = "gfx/models/test/test.asset";
+    let report = take_report(&mut reports, asset, "state `walk` is not defined in this entity");
+    report.expect("entity next_state");
+    let msg = "animation `test_run` is not defined in pdxmesh `test_mesh`";
+    let report = take_report(&mut reports, asset, msg);
+    report.expect("entity state animation");
+    let msg = "no event in this entity has attachment_id `lamp`";
+    let report = take_report(&mut reports, asset, msg);
+    report.expect("entity remove_attachment");
+    let objects = "gfx/map/map_object_data/test_objects.txt";
+    let msg = "pdxmesh no_such_mesh not defined in gfx/models/";
+    let report = take_report(&mut reports, objects, msg);
+    report.expect("map object pdxmesh");
+    let dna = "common/dna_data/test_dna.txt";
+    let report = take_report(&mut reports, dna, "could not decode DNA string");
+    let report = report.expect("dna string not base64");
+    assert!(report.confidence == Confidence::Weak);
+    let msg = "DNA string has 4 bytes, but the 2 defined genes need 8";
+    let report = take_report(&mut reports, dna, msg);
+    let report = report.expect("dna string for old genes");
+    assert!(report.pointers[0].loc.line == 6);
+    assert!(report.confidence == Confidence::Weak);
+    let msg = "DNA string uses template index 5 of gene test_morph";
+    let report = take_report(&mut reports, dna, msg);
+    let report = report.expect("dna string template index");
+    assert!(report.confidence == Confidence::Weak);
 
     dbg!(&reports);
     assert!(reports.is_empty());
@@ -148,6 +370,403 @@ fn test_mod2() {
         take_report(&mut reports, lists, "`courtier_parent` expects scope:child to be set");
     report.expect("scope check for scripted lists");
 
+    let characters = "history/characters/test.txt";
+    let report = take_report(
+        &mut reports,
+        characters,
+        "father test_father died on 1044.1.1, before test_child was conceived",
+    );
+    report.expect("parent alive check");
+    let report = take_report(
+        &mut reports,
+        characters,
+        "marriage of test_father and test_mother is on 1040.1.1 here but on 1041.1.1 in the spouse's history",
+    );
+    report.expect("symmetric marriage check");
+    let report = take_report(
+        &mut reports,
+        characters,
+        "test_mother was not yet 16 when marrying test_father on 1040.1.1",
+    );
+    report.expect("spouse age check");
+    let report = take_report(&mut reports, characters, "test_late_spouse is not alive on 1065.1.1");
+    report.expect("spouse alive check");
+    let report = take_report(
+        &mut reports,
+        characters,
+        "marriage of test_child and test_late_spouse on 1065.1.1 is not recorded in test_late_spouse's history",
+    );
+    report.expect("one-sided marriage check");
+    let report = take_report(
+        &mut reports,
+        characters,
+        "test_child was not alive on 1071.1.1, had already died on 1070.1.1",
+    );
+    report.expect("own alive at marriage check");
+    let report = take_report(
+        &mut reports,
+        characters,
+        "marriage of test_child and test_mother on 1071.1.1 is not recorded in test_mother's history",
+    );
+    report.expect("one-sided marriage check for the later marriage");
+
+    let genealogy = "history/characters/test_genealogy.txt";
+    let msg = "test_gen_house_child is of dynasty test_dynasty_b, but neither parent is";
+    let report = take_report(&mut reports, genealogy, msg);
+    let report = report.expect("dynasty from parents check");
+    assert!(report.info.as_deref() == Some("father test_gen_father is of dynasty test_dynasty_a"));
+    let msg = "house test_house_a belongs to dynasty test_dynasty_a, not test_dynasty_b";
+    let report = take_report(&mut reports, genealogy, msg);
+    report.expect("house and dynasty mismatch check");
+    for parent in ["father test_gen_father", "mother test_gen_mother"] {
+        let msg = format!("{parent} was born on 1000.1.1, less than 12 years before test_gen_young_child on 1005.1.1");
+        let report = take_report(&mut reports, genealogy, &msg);
+        report.expect("minimum parent age check");
+    }
+    let msg = "mother test_gen_mother was born on 1000.1.1, more than 60 years before test_gen_late_child on 1065.1.1";
+    let report = take_report(&mut reports, genealogy, msg);
+    let report = report.expect("maximum mother age check");
+    assert!(report.pointers[0].loc.line == 41);
+
+    let titles = "history/titles/test.txt";
+    let mut cycles: Vec<_> = (0..2)
+        .map(|_| {
+            let report = take_report(&mut reports, titles, "de jure liege cycle on 1000.1.1");
+            report.expect("de jure cycle").info.unwrap()
+        })
+        .collect();
+    cycles.sort();
+    assert!(cycles == ["via e_test_b, k_test_a", "via k_test_a, e_test_b"]);
+    let report = take_report(&mut reports, titles, "liege must be higher tier than e_test_b");
+    report.expect("de jure liege tier");
+    let msg = "test_father dies on 1044.1.1 while still holding k_test_c";
+    let report = take_report(&mut reports, titles, msg);
+    let report = report.expect("holder dies while holding");
+    assert!(report.info.as_deref() == Some("the next holder is only set on 1050.1.1"));
+
+    let bookmarks = "common/bookmarks/bookmarks/test.txt";
+    let msg = "character test_father is not alive on the bookmark date 1050.1.1";
+    let report = take_report(&mut reports, bookmarks, msg);
+    report.expect("bookmark character alive");
+    let msg = "type is male in bookmark but character is a boy at 1050.1.1";
+    let report = take_report(&mut reports, bookmarks, msg);
+    report.expect("bookmark character age");
+    let msg = "birth is 1044.1.1 in bookmark but 1045.1.1 in character history";
+    let report = take_report(&mut reports, bookmarks, msg);
+    report.expect("bookmark birth date");
+    let msg = "test_mother does not hold k_test_c on 1050.1.1";
+    let report = take_report(&mut reports, bookmarks, msg);
+    let report = report.expect("bookmark title holder");
+    assert!(report.info.as_deref() == Some("it is held by test_child"));
+    let msg = "type is female in bookmark but male in portrait";
+    let report = take_report(&mut reports, bookmarks, msg);
+    let report = report.expect("bookmark portrait type");
+    assert!(report.pointers[1].loc.pathname() == Path::new("common/bookmark_portraits/test.txt"));
+    let portraits = "common/bookmark_portraits/test.txt";
+    let msg = "bookmark portrait genes differ from dna test_mother_dna of character test_mother";
+    let report = take_report(&mut reports, portraits, msg);
+    let report = report.expect("bookmark portrait genes");
+    assert!(report.info.as_deref() == Some("differing genes: skin_color"));
+    let msg = "portrait animation test_animation has no animation for a female";
+    let report = take_report(&mut reports, bookmarks, msg);
+    let report = report.expect("bookmark animation body type");
+    assert!(report.info.as_deref() == Some("followed body types female and girl"));
+
+    dbg!(&reports);
+    assert!(reports.is_empty());
+}
+
+#[test]
+fn test_mod3() {
+    let mut reports = check_mod_helper("mod3");
+
+    let german = "localization/german/test_l_german.yml";
+    let report =
+        take_report(&mut reports, german, "german localization does not match the english version");
+    let report = report.expect("translation parity test");
+    assert_eq!(
+        report.info.as_deref(),
+        Some(
+            "missing `$VALUE$`, `[ROOT.Char.GetName]` and `@gold_icon!`; not in english: `$WERT$` and `[ROOT.Char.GetNom]`"
+        )
+    );
+    assert!(
+        report.pointers[1].loc.pathname() == Path::new("localization/english/test_l_english.yml")
+    );
+    let report = take_report_contains(&mut reports, german, "GetNom");
+    report.expect("translated code chain is still validated");
+    let english = "localization/english/test_l_english.yml";
+    let report = take_report(&mut reports, english, "text icon gold_icon not defined in gui/");
+    report.expect("text icon test");
+
+    dbg!(&reports);
+    assert!(reports.is_empty());
+}
+
+#[test]
+fn test_mod4() {
+    let mut reports = check_mod_helper("mod4");
+
+    let english = "localization/english/test_l_english.yml";
+    let msg = "characters `z`, `½` and `q` are not in the english fonts";
+    let report = take_report(&mut reports, english, msg);
+    let report = report.expect("glyphs missing from ttf and text fnt");
+    assert!(report.pointers[0].loc.column == 23);
+    let german = "localization/german/test_l_german.yml";
+    let msg = "characters `二` and `ä` are not in the german fonts";
+    let report = take_report(&mut reports, german, msg);
+    report.expect("glyphs missing from otf and binary fnt");
+
+    dbg!(&reports);
+    assert!(reports.is_empty());
+}
+
+#[test]
+fn test_mod3_loca_coverage() {
+    let _guard = TEST_MUTEX.lock().unwrap();
+
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let mod_root = PathBuf::from("tests/files/mod3");
+    let mut everything = Everything::new(Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.load_all();
+    take_reports();
+
+    let report = everything.loca_coverage_report();
+    assert!(report.starts_with("Localization coverage of 3 english keys:\n"));
+    assert!(report.contains("  german: 2 translated, 0 untranslated, 1 stale\n"));
+    assert!(report.contains("  french: 0 translated, 3 untranslated, 0 stale\n"));
+
+    let dir = std::env::temp_dir().join("tiger-test-loca-stubs");
+    let written = everything.write_loca_stubs(&dir).unwrap();
+    assert!(!written.contains(&dir.join("german/untranslated_l_german.yml")));
+    let french = std::fs::read_to_string(dir.join("french/untranslated_l_french.yml")).unwrap();
+    assert!(french.starts_with("\u{feff}l_french:\n"));
+    assert!(french.contains(" test_same:1 \"[ROOT.Char.GetName] is here\" #NT!\n"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_mod1_gui_tree() {
+    let _guard = TEST_MUTEX.lock().unwrap();
+
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let mod_root = PathBuf::from("tests/files/mod1");
+    let mut everything = Everything::new(Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.load_all();
+    everything.validate_all();
+    take_reports();
+
+    let tree = everything.gui_tree("test_box_instance").unwrap();
+    assert!(tree.starts_with("test_box = {  # gui/test-blockoverride.gui:12 (Mod)\n"));
+    assert!(tree.contains("    # size = { 10 10 }  # gui/test-blockoverride.gui:3 (Mod)\n"));
+    assert!(tree.contains("    size = { 20 20 }  # gui/test-blockoverride.gui:14 (Mod)\n"));
+    assert!(tree.contains("        name = replaced  # gui/test-blockoverride.gui:17 (Mod)\n"));
+    assert!(!tree.contains("original"));
+    assert!(everything.gui_tree("no_such_widget").is_none());
+    assert!(take_reports().is_empty());
+}
+
+#[test]
+fn test_mod1_zipped() {
+    let summarize = |reports: Vec<LogReport>| {
+        let mut summary: Vec<_> = reports
+            .iter()
+            .map(|r| (r.msg.clone(), r.pointers[0].loc.pathname(), r.pointers[0].loc.line))
+            .collect();
+        summary.sort();
+        summary
+    };
+    let from_dir = summarize(check_mod_helper("mod1"));
+    let from_zip = summarize(check_zipped_mod_helper("mod1"));
+    assert_eq!(from_dir, from_zip);
+}
+
+#[test]
+fn test_mod5() {
+    let mut reports = check_mod_helper("mod5");
+
+    let decisions = "common/decisions/test.txt";
+    let msg = "file gfx/Portable.jpg only exists as gfx/portable.jpg";
+    let report = take_report(&mut reports, decisions, msg);
+    let report = report.expect("file reference case mismatch");
+    assert!(report.pointers[0].loc.line == 2);
+    let long = "gfx/a_directory_name_that_is_long_enough_to_push_the_full_path_over_the_limit/another_directory_name_that_is_long_enough_to_push_the_full_path_over/and_one_more_directory_name_to_make_sure_we_get_there/this_file_name_is_long_enough_to_cross_the_limit.txt";
+    let report = take_report(&mut reports, long, "full path of this file is 271 characters long");
+    report.expect("over-long path");
+
     dbg!(&reports);
     assert!(reports.is_empty());
 }
+
+#[test]
+fn test_mod6_overrides() {
+    let _guard = TEST_MUTEX.lock().unwrap();
+
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let mod_root = PathBuf::from("tests/files/mod6");
+    let mut everything = Everything::new(Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.load_config_filtering_rules();
+    everything.load_all();
+    everything.validate_all();
+    let mut reports = take_reports();
+    // Reset the overrides for the other tests
+    Everything::new(Some(&vanilla_dir), Path::new("tests/files/mod3"), Vec::new())
+        .unwrap()
+        .load_config_filtering_rules();
+
+    let config = "ck3-tiger.conf";
+    let report = take_report_contains(&mut reports, config, "Invalid key.");
+    let report = report.expect("unknown key in severity_override");
+    assert!(report.pointers[0].loc.line == 6);
+    let report = take_report_contains(&mut reports, config, "Invalid Severity value.");
+    let report = report.expect("unknown severity in severity_override");
+    assert!(report.pointers[0].loc.line == 7);
+
+    let msg = "required field `picture` missing";
+    for (file, severity) in [
+        // only the global override applies
+        ("common/decisions/plain.txt", Severity::Tips),
+        // `?` matches exactly one character
+        ("common/decisions/test_a.txt", Severity::Untidy),
+        ("common/decisions/test_ab.txt", Severity::Tips),
+        // `*` matches within one path component
+        ("common/decisions/glob_one.txt", Severity::Warning),
+        // `**` matches across path components, and the later `*` rule does not match here
+        ("common/decisions/deep/glob_two.txt", Severity::Error),
+    ] {
+        let report = take_report(&mut reports, file, msg);
+        let report = report.expect("severity override");
+        assert_eq!(report.severity, severity, "{file}");
+    }
+
+    dbg!(&reports);
+    assert!(reports.is_empty());
+}
+
+#[test]
+fn test_mod7_html() {
+    let _guard = TEST_MUTEX.lock().unwrap();
+
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let mod_root = PathBuf::from("tests/files/mod7");
+    let mut everything = Everything::new(Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.load_all();
+    everything.validate_all();
+
+    let path = std::env::temp_dir().join("tiger-test-mod7.html");
+    set_output_file(&path).unwrap();
+    collect_abbreviated_reports();
+    everything.check_unused();
+    emit_reports_html();
+    let html = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(html.contains("<span class=\"msg\">unknown datafunction GetNom</span>"));
+    // The source line is escaped and the whole multi-character pointer is marked
+    let line =
+        " test_html:0 &quot;Fish &amp; &lt;chips&gt; for [ROOT.Char.<mark>GetNom</mark>]&quot;";
+    assert!(html.contains(&format!("<span class=\"linenr\">2 | </span>{line}</pre>")));
+    assert!(html.contains("<span id=\"shown\">1</span> of 1 reports"));
+    // The unused localization is listed inside the page, after the reports
+    let unused = "(unused-localization)  test_html:0 &quot;Fish &amp; &lt;chips&gt; for [ROOT.Char.GetNom]&quot;\n";
+    let section = html.find("<h2>Unused items</h2>").expect("unused section");
+    assert!(html[section..].contains(unused));
+    assert!(html.ends_with("</html>\n"));
+}
+
+fn summary_helper(keys: &[(&str, usize)], items: &[(&str, usize)]) -> Summary {
+    let mut summary = Summary::default();
+    for (key, count) in keys {
+        summary.reports.total += count;
+        *summary.reports.severity.entry("warning".to_string()).or_default() += count;
+        *summary.reports.confidence.entry("reasonable".to_string()).or_default() += count;
+        summary.reports.key.insert((*key).to_string(), *count);
+    }
+    summary.items = items.iter().map(|(item, count)| ((*item).to_string(), *count)).collect();
+    summary.mod_files = 4;
+    summary
+}
+
+#[test]
+fn test_summary_round_trip() {
+    let mut summary = summary_helper(&[("missing-item", 3), ("validation", 1)], &[("event", 2)]);
+    summary.top_files = vec![("events/test.txt".to_string(), 4)];
+    summary.phases = vec![("load".to_string(), 1.5), ("validate".to_string(), 0.25)];
+
+    let path = std::env::temp_dir().join("tiger-test-summary.json");
+    summary.write(&path).unwrap();
+    let read = Summary::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(format!("{summary:?}"), format!("{read:?}"));
+}
+
+#[test]
+fn test_summary_deltas() {
+    let previous = summary_helper(&[("missing-item", 3), ("validation", 1)], &[("event", 2)]);
+    let current = summary_helper(&[("missing-item", 3), ("scopes", 2)], &[("event", 2)]);
+    let deltas = current.deltas(&previous);
+    assert_eq!(
+        deltas,
+        "Changes since the previous run:\n  reports: 4 -> 5 (+1)\n  warning: 4 -> 5 (+1)\n  reasonable confidence: 4 -> 5 (+1)\n  (scopes): 0 -> 2 (+2)\n  (validation): 1 -> 0 (-1)\n"
+    );
+    assert_eq!(current.deltas(&current.clone()), "No changes since the previous run.\n");
+}
+
+#[test]
+fn test_mod8_unused_overrides() {
+    let _guard = TEST_MUTEX.lock().unwrap();
+
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let mod_root = PathBuf::from("tests/files/mod8");
+    let mut everything = Everything::new(Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.load_config_filtering_rules();
+    everything.load_all();
+    everything.validate_all();
+    take_reports();
+
+    let path = std::env::temp_dir().join("tiger-test-mod8.txt");
+    set_output_file(&path).unwrap();
+    everything.check_unused();
+    let output = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    // Reset the filter and overrides for the other tests
+    Everything::new(Some(&vanilla_dir), Path::new("tests/files/mod3"), Vec::new())
+        .unwrap()
+        .load_config_filtering_rules();
+
+    assert!(output.contains("(unused-localization)  loud_unused:0 \"Loud\"\n"));
+    // The override lowers this one to untidy, which the filter hides
+    assert!(!output.contains("quiet_unused"));
+}
+
+#[test]
+fn test_mod1_unused() {
+    let _guard = TEST_MUTEX.lock().unwrap();
+
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let mod_root = PathBuf::from("tests/files/mod1");
+    let mut everything = Everything::new(Some(&vanilla_dir), &mod_root, Vec::new()).unwrap();
+    everything.load_all();
+    everything.validate_all();
+    take_reports();
+
+    let path = std::env::temp_dir().join("tiger-test-mod1-unused.txt");
+    set_output_file(&path).unwrap();
+    everything.check_unused();
+    let output = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(output.contains("(unused-gui) template unused_template {\n"));
+    // Only used by an unused type
+    assert!(output.contains("(unused-gui) template dead_template {\n"));
+    assert!(output.contains("(unused-gui) \ttype unused_type = widget {\n"));
+    assert!(!output.contains("(unused-gui) template used_template {\n"));
+    assert!(!output.contains("type used_type"));
+
+    assert!(output.contains("(unused-asset) \tname = \"test_unused_entity\"\n"));
+    assert!(output.contains("(unused-asset) \tname = \"test_unused_mesh\"\n"));
+    // Used by test_entity
+    assert!(!output.contains("\"test_mesh\""));
+    // Replaces a vanilla entity, which the game may be using
+    assert!(!output.contains("test_override_entity"));
+}